@@ -9,6 +9,7 @@ use lightmotif::abc::Alphabet;
 use lightmotif::dense::DenseMatrix;
 use lightmotif::num::Unsigned;
 use lightmotif::pwm::ScoringMatrix;
+use lightmotif::variant::VariantEffect;
 
 #[cfg(feature = "fnv")]
 use fnv::FnvHashMap;
@@ -292,6 +293,20 @@ impl<'pssm, A: Alphabet> TfmPvalue<'pssm, A> {
         it.score
     }
 
+    /// Compute the P-values of the reference and alternate sites of a variant.
+    ///
+    /// The effect must have been computed with the scoring matrix of this
+    /// instance. Alleles without a site overlapping the variant have no
+    /// P-value.
+    pub fn variant_pvalues(&mut self, effect: &VariantEffect) -> (Option<f64>, Option<f64>) {
+        let reference = effect.reference.as_ref().map(|hit| hit.score);
+        let alternate = effect.alternate.as_ref().map(|hit| hit.score);
+        (
+            reference.map(|score| self.pvalue(score as f64)),
+            alternate.map(|score| self.pvalue(score as f64)),
+        )
+    }
+
     /// Iterate with decreasing granularity to compute an approximate score for a P-value.
     pub fn approximate_score(&mut self, pvalue: f64) -> ScoresIterator<'pssm, '_, A> {
         self.recompute(0.1);
@@ -533,4 +548,29 @@ mod test {
         assert_almost_eq!(tfmp.score(0.0003), 8.882756, places = 5);
         assert_almost_eq!(tfmp.score(1e-10), 19.1, places = 5);
     }
+
+    #[test]
+    fn variant_pvalues() {
+        use lightmotif::pli::Pipeline;
+        use lightmotif::seq::EncodedSequence;
+        use lightmotif::variant::Variant;
+
+        let pssm = build_ma0045();
+        let mut tfmp = TfmPvalue::new(&pssm);
+
+        // mutate the most informative position of the consensus site
+        let sequence = format!("GG{}GG", pssm.consensus());
+        let sequence = EncodedSequence::<Dna>::encode(&sequence).unwrap();
+        let variant = Variant::encode(2 + 4, "A", "C").unwrap();
+        let pli = Pipeline::generic();
+        let effects = variant
+            .effects::<lightmotif::num::U32, _>(&pli, &sequence, &[pssm.clone()])
+            .unwrap();
+
+        let (reference, alternate) = tfmp.variant_pvalues(&effects[0]);
+        let reference = reference.unwrap();
+        let alternate = alternate.unwrap();
+        assert!(reference < alternate);
+        assert_eq!(reference, tfmp.pvalue(pssm.max_score() as f64));
+    }
 }
@@ -21,7 +21,7 @@ pub struct Matrix<A: Alphabet> {
     accession: Option<String>,
     name: Option<String>,
     description: Option<String>,
-    counts: CountMatrix<A>,
+    counts: CountMatrix<A, f32>,
     dates: Vec<Date>,
     references: Vec<Reference>,
     sites: Vec<String>,
@@ -45,8 +45,8 @@ impl<A: Alphabet> Matrix<A> {
     }
 }
 
-impl<A: Alphabet> AsRef<CountMatrix<A>> for Matrix<A> {
-    fn as_ref(&self) -> &CountMatrix<A> {
+impl<A: Alphabet> AsRef<CountMatrix<A, f32>> for Matrix<A> {
+    fn as_ref(&self) -> &CountMatrix<A, f32> {
         &self.counts
     }
 }
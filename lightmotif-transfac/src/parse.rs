@@ -14,6 +14,7 @@ use nom::character::streaming::space1;
 use nom::combinator::eof;
 use nom::combinator::map_res;
 use nom::combinator::opt;
+use nom::combinator::verify;
 use nom::error::Error;
 use nom::error::ErrorKind;
 use nom::multi::count;
@@ -63,14 +64,13 @@ pub fn parse_alphabet<S: Symbol>(input: &str) -> IResult<&str, Vec<S>> {
     )(input)
 }
 
-pub fn parse_element(input: &str) -> IResult<&str, u32> {
-    terminated(
-        nom::character::complete::u32,
-        opt(preceded(char('.'), many0(char('0')))),
-    )(input)
+pub fn parse_element(input: &str) -> IResult<&str, f32> {
+    verify(nom::number::complete::float, |x: &f32| {
+        x.is_finite() && *x >= 0.0
+    })(input)
 }
 
-pub fn parse_row(input: &str, k: usize) -> IResult<&str, Vec<u32>> {
+pub fn parse_row(input: &str, k: usize) -> IResult<&str, Vec<f32>> {
     delimited(
         nom::character::complete::u32,
         count(delimited(space0, parse_element, space0), k),
@@ -267,7 +267,7 @@ pub fn parse_matrix<A: Alphabet>(mut input: &str) -> IResult<&str, Matrix<A>> {
                 let (rest, counts) = many1(|l| parse_row(l, symbols.len()))(rest)?;
                 input = rest;
                 // read counts into a dense matrix
-                let mut data = DenseMatrix::<u32, A::K>::new(counts.len());
+                let mut data = DenseMatrix::<f32, A::K>::new(counts.len());
                 for (i, count) in counts.iter().enumerate() {
                     for (s, &c) in symbols.iter().zip(count.iter()) {
                         data[i][s.as_index()] = c;
@@ -289,7 +289,8 @@ pub fn parse_matrix<A: Alphabet>(mut input: &str) -> IResult<&str, Matrix<A>> {
         }
     }
 
-    let counts = CountMatrix::new(countmatrix.unwrap()).unwrap();
+    let counts = CountMatrix::new(countmatrix.unwrap())
+        .map_err(|_| nom::Err::Failure(Error::new(input, ErrorKind::Verify)))?;
     let matrix = Matrix {
         accession,
         id,
@@ -336,7 +337,19 @@ mod test {
         let line = "00      0      0      2      0      G\n";
         let res = super::parse_row(line, 4).unwrap();
         assert_eq!(res.0, "");
-        assert_eq!(res.1, vec![0, 0, 2, 0]);
+        assert_eq!(res.1, vec![0.0, 0.0, 2.0, 0.0]);
+
+        let line = "01   0.25   0.5   0.125   0.125   N\n";
+        let res = super::parse_row(line, 4).unwrap();
+        assert_eq!(res.0, "");
+        assert_eq!(res.1, vec![0.25, 0.5, 0.125, 0.125]);
+
+        let line = "02      0     -3      2      0      G\n";
+        assert!(super::parse_row(line, 4).is_err());
+        let line = "02      0    nan      2      0      G\n";
+        assert!(super::parse_row(line, 4).is_err());
+        let line = "02      0    inf      2      0      G\n";
+        assert!(super::parse_row(line, 4).is_err());
     }
 
     #[test]
@@ -361,16 +374,16 @@ mod test {
         let matrix = res.1;
         assert_eq!(matrix.id, Some(String::from("prodoric_MX000001")));
         assert_eq!(matrix.counts.counts().rows(), 7);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::A.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::T.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::G.as_index()], 2);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::C.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::N.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::A.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::T.as_index()], 1);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::G.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::C.as_index()], 1);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::N.as_index()], 0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::A.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::T.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::G.as_index()], 2.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::C.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::N.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::A.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::T.as_index()], 1.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::G.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::C.as_index()], 1.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::N.as_index()], 0.0);
     }
 
     #[test]
@@ -457,16 +470,16 @@ mod test {
         let matrix = res.1;
         assert_eq!(matrix.accession, Some(String::from("M00001")));
         assert_eq!(matrix.counts.counts().rows(), 12);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::A.as_index()], 1);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::T.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::G.as_index()], 2);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::C.as_index()], 2);
-        assert_eq!(matrix.counts.counts()[0][Nucleotide::N.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::A.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::T.as_index()], 1);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::G.as_index()], 4);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::C.as_index()], 0);
-        assert_eq!(matrix.counts.counts()[5][Nucleotide::N.as_index()], 0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::A.as_index()], 1.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::T.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::G.as_index()], 2.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::C.as_index()], 2.0);
+        assert_eq!(matrix.counts.counts()[0][Nucleotide::N.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::A.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::T.as_index()], 1.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::G.as_index()], 4.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::C.as_index()], 0.0);
+        assert_eq!(matrix.counts.counts()[5][Nucleotide::N.as_index()], 0.0);
     }
 
     #[test]
@@ -46,6 +46,12 @@ mod dna {
         bench::<U32, _>(bencher, &pli);
     }
 
+    #[bench]
+    fn bench_table(bencher: &mut test::Bencher) {
+        let pli = Pipeline::table();
+        bench::<U32, _>(bencher, &pli);
+    }
+
     #[cfg(target_feature = "sse2")]
     #[bench]
     fn bench_sse2(bencher: &mut test::Bencher) {
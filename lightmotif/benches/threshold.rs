@@ -47,6 +47,12 @@ fn bench_generic(bencher: &mut test::Bencher) {
     bench::<U32, _>(bencher, &pli);
 }
 
+#[bench]
+fn bench_table(bencher: &mut test::Bencher) {
+    let pli = Pipeline::table();
+    bench::<U32, _>(bencher, &pli);
+}
+
 #[cfg(target_feature = "sse2")]
 #[bench]
 fn bench_sse2(bencher: &mut test::Bencher) {
@@ -0,0 +1,414 @@
+//! Hierarchical clustering of motifs and archetype construction.
+//!
+//! Motifs are compared pairwise using the best ungapped alignment of their
+//! columns on both strands (see [`compare::align`]), and the Pearson
+//! correlation of the aligned columns is normalized by the width of the
+//! alignment so that partially overlapping motifs are penalized. Motifs are
+//! then clustered with average linkage, and the members of each cluster can
+//! be merged into an archetype motif.
+//!
+//! [`compare::align`]: crate::compare::align
+
+use super::abc::ComplementableAlphabet;
+use super::compare::align;
+use super::compare::Alignment;
+use super::compare::Metric;
+use super::dense::DenseMatrix;
+use super::pwm::Count;
+use super::pwm::CountMatrix;
+use super::pwm::FrequencyMatrix;
+use super::seq::Strand;
+use super::util::find;
+
+// --- Similarity --------------------------------------------------------------
+
+/// Compute the normalized similarity between two motifs.
+///
+/// The similarity is the sum of the Pearson correlations of the columns in
+/// the best alignment of the motifs, divided by the total width of the
+/// alignment, including the overhangs of both motifs. It is `1.0` for
+/// identical motifs, and is penalized by the number of unaligned columns.
+/// Returns the similarity along with the alignment, or `None` if the motifs
+/// cannot be aligned with at least `min_overlap` columns.
+pub fn similarity<A: ComplementableAlphabet>(
+    a: &FrequencyMatrix<A>,
+    b: &FrequencyMatrix<A>,
+    min_overlap: usize,
+) -> Option<(f32, Alignment)> {
+    let m = AsRef::<DenseMatrix<f32, A::K>>::as_ref(a).rows();
+    let n = AsRef::<DenseMatrix<f32, A::K>>::as_ref(b).rows();
+    align(a, b, Metric::Pearson, min_overlap).map(|aln| {
+        let width = m + n - aln.overlap;
+        (aln.score / width as f32, aln)
+    })
+}
+
+// --- Cluster -----------------------------------------------------------------
+
+/// A member of a motif cluster, placed relative to the cluster reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Member {
+    /// The index of the motif in the clustered collection.
+    pub index: usize,
+    /// The strand of the motif relative to the reference.
+    pub strand: Strand,
+    /// The position of the first column of the motif, on the given strand,
+    /// relative to the first column of the reference.
+    pub offset: isize,
+}
+
+/// A cluster of similar motifs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    /// The index of the reference motif used to align the members.
+    ///
+    /// The reference is the medoid of the cluster, i.e. the member with
+    /// the highest total similarity to the other members.
+    pub reference: usize,
+    /// The members of the cluster, including the reference.
+    pub members: Vec<Member>,
+}
+
+impl Cluster {
+    /// Compute the range of archetype columns, relative to the reference.
+    fn span<F: Fn(usize) -> usize>(&self, length: F) -> (isize, usize) {
+        let start = self.members.iter().map(|m| m.offset).min().unwrap_or(0);
+        let end = self
+            .members
+            .iter()
+            .map(|m| m.offset + length(m.index) as isize)
+            .max()
+            .unwrap_or(0);
+        (start, (end - start) as usize)
+    }
+
+    /// Build an archetype by summing the aligned counts of the members.
+    ///
+    /// Columns of the archetype which are not covered by a member receive
+    /// no counts from it, so the total count of the archetype columns may
+    /// vary on the flanks.
+    pub fn archetype_counts<A: ComplementableAlphabet, T: Count>(
+        &self,
+        motifs: &[CountMatrix<A, T>],
+    ) -> CountMatrix<A, T> {
+        let (start, width) = self.span(|i| motifs[i].counts().rows());
+        let mut data = DenseMatrix::<T, A::K>::new(width);
+        for member in &self.members {
+            let rc;
+            let counts = match member.strand {
+                Strand::Direct => motifs[member.index].counts(),
+                Strand::Reverse => {
+                    rc = motifs[member.index].reverse_complement();
+                    rc.counts()
+                }
+            };
+            let first = (member.offset - start) as usize;
+            for (i, row) in counts.iter().enumerate() {
+                for (x, &c) in data[first + i].iter_mut().zip(row) {
+                    *x += c;
+                }
+            }
+        }
+        CountMatrix::new(data).unwrap()
+    }
+
+    /// Build an archetype by averaging the aligned frequencies of the members.
+    ///
+    /// Each column of the archetype is the average of the member columns
+    /// aligned to it, ignoring members which do not cover the column.
+    pub fn archetype<A: ComplementableAlphabet>(
+        &self,
+        motifs: &[FrequencyMatrix<A>],
+    ) -> FrequencyMatrix<A> {
+        let rows = |i: usize| AsRef::<DenseMatrix<f32, A::K>>::as_ref(&motifs[i]).rows();
+        let (start, width) = self.span(rows);
+        let mut data = DenseMatrix::<f32, A::K>::new(width);
+        let mut coverage = vec![0usize; width];
+        for member in &self.members {
+            let rc;
+            let freqs: &DenseMatrix<f32, A::K> = match member.strand {
+                Strand::Direct => motifs[member.index].as_ref(),
+                Strand::Reverse => {
+                    rc = motifs[member.index].reverse_complement();
+                    rc.as_ref()
+                }
+            };
+            let first = (member.offset - start) as usize;
+            for (i, row) in freqs.iter().enumerate() {
+                coverage[first + i] += 1;
+                for (x, &f) in data[first + i].iter_mut().zip(row) {
+                    *x += f;
+                }
+            }
+        }
+        for (row, &n) in data.iter_mut().zip(coverage.iter()) {
+            for x in row.iter_mut() {
+                *x /= n as f32;
+            }
+        }
+        FrequencyMatrix::new_unchecked(data)
+    }
+}
+
+// --- Clustering --------------------------------------------------------------
+
+/// Cluster motifs hierarchically using average linkage.
+///
+/// The pairwise [`similarity`] of all motifs is computed, pairs which
+/// cannot be aligned with `min_overlap` columns receiving the lowest
+/// similarity of `-1.0`. The complete dendrogram is built with the
+/// nearest-neighbor chain algorithm, and cut so that every cluster is
+/// formed of merges with an average similarity of at least `threshold`.
+/// Members which cannot be aligned with `min_overlap` columns to any other
+/// member of their cluster, which can only happen for a threshold of `-1.0`
+/// or lower, are split into separate clusters.
+///
+/// Members are aligned starting from the reference, each relative to its
+/// most similar member already aligned, so that chains of overlapping
+/// motifs are placed consistently even if their ends do not overlap.
+/// Clusters are returned in order of their smallest member index.
+pub fn cluster<A: ComplementableAlphabet>(
+    motifs: &[FrequencyMatrix<A>],
+    threshold: f32,
+    min_overlap: usize,
+) -> Vec<Cluster> {
+    let n = motifs.len();
+
+    // compute pairwise similarities
+    let mut sim = vec![vec![-1.0f32; n]; n];
+    let mut overlapping = vec![vec![false; n]; n];
+    for i in 0..n {
+        sim[i][i] = 1.0;
+        overlapping[i][i] = true;
+        for j in i + 1..n {
+            if let Some((s, _)) = similarity(&motifs[i], &motifs[j], min_overlap) {
+                sim[i][j] = s;
+                sim[j][i] = s;
+                overlapping[i][j] = true;
+                overlapping[j][i] = true;
+            }
+        }
+    }
+
+    // build the dendrogram with the nearest-neighbor chain algorithm,
+    // recording merges with a similarity above the threshold
+    let mut linkage = sim.clone();
+    let mut sizes = vec![1usize; n];
+    let mut active = vec![true; n];
+    let mut parent = (0..n).collect::<Vec<usize>>();
+    let mut chain: Vec<usize> = Vec::new();
+    for _ in 1..n {
+        loop {
+            if chain.is_empty() {
+                chain.push(active.iter().position(|&x| x).unwrap());
+            }
+            let a = *chain.last().unwrap();
+            let previous = chain.len().checked_sub(2).map(|i| chain[i]);
+            let mut b = previous.unwrap_or(a);
+            for x in (0..n).filter(|&x| active[x] && x != a) {
+                if b == a || linkage[a][x] > linkage[a][b] {
+                    b = x;
+                }
+            }
+            if Some(b) == previous {
+                chain.truncate(chain.len() - 2);
+                if linkage[a][b] >= threshold {
+                    let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+                    parent[ra.max(rb)] = ra.min(rb);
+                }
+                // merge b into a using the Lance-Williams update
+                for x in (0..n).filter(|&x| active[x] && x != a && x != b) {
+                    let s = (sizes[a] as f32 * linkage[a][x] + sizes[b] as f32 * linkage[b][x])
+                        / (sizes[a] + sizes[b]) as f32;
+                    linkage[a][x] = s;
+                    linkage[x][a] = s;
+                }
+                sizes[a] += sizes[b];
+                active[b] = false;
+                break;
+            } else {
+                chain.push(b);
+            }
+        }
+    }
+
+    // collect clusters from the merge forest, splitting the members which
+    // cannot be aligned to each other, directly or through other members
+    let mut components = (0..n).collect::<Vec<usize>>();
+    for (i, row) in overlapping.iter().enumerate() {
+        for j in (i + 1..n).filter(|&j| row[j]) {
+            if find(&mut parent, i) == find(&mut parent, j) {
+                let (ri, rj) = (find(&mut components, i), find(&mut components, j));
+                components[ri.max(rj)] = ri.min(rj);
+            }
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of = vec![usize::MAX; n];
+    for i in 0..n {
+        let root = find(&mut components, i);
+        if group_of[root] == usize::MAX {
+            group_of[root] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[group_of[root]].push(i);
+    }
+
+    // align the members of each cluster starting from the medoid, placing
+    // each member relative to its most similar member already placed, with
+    // the same minimum overlap as used to compute the similarities
+    groups
+        .into_iter()
+        .map(|group| {
+            let reference = *group
+                .iter()
+                .max_by(|&&x, &&y| {
+                    let sx: f32 = group.iter().map(|&j| sim[x][j]).sum();
+                    let sy: f32 = group.iter().map(|&j| sim[y][j]).sum();
+                    sx.total_cmp(&sy).then(y.cmp(&x))
+                })
+                .unwrap();
+            let mut placed = vec![None; n];
+            placed[reference] = Some(Member {
+                index: reference,
+                strand: Strand::Direct,
+                offset: 0,
+            });
+            for _ in 1..group.len() {
+                let (anchor, index) = group
+                    .iter()
+                    .filter(|&&x| placed[x].is_some())
+                    .flat_map(|&x| group.iter().map(move |&y| (x, y)))
+                    .filter(|&(x, y)| placed[y].is_none() && overlapping[x][y])
+                    .max_by(|&(a, b), &(c, d)| sim[a][b].total_cmp(&sim[c][d]))
+                    .unwrap();
+                let anchor = placed[anchor].clone().unwrap();
+                let rc;
+                let query = match anchor.strand {
+                    Strand::Direct => &motifs[anchor.index],
+                    Strand::Reverse => {
+                        rc = motifs[anchor.index].reverse_complement();
+                        &rc
+                    }
+                };
+                let aln = align(query, &motifs[index], Metric::Pearson, min_overlap).unwrap();
+                placed[index] = Some(Member {
+                    index,
+                    strand: aln.strand,
+                    offset: anchor.offset - aln.offset,
+                });
+            }
+            let members = group
+                .iter()
+                .map(|&index| placed[index].take().unwrap())
+                .collect();
+            Cluster { reference, members }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::seq::EncodedSequence;
+
+    fn build_counts(sequences: &[&str]) -> CountMatrix<Dna> {
+        CountMatrix::<Dna>::from_sequences(
+            sequences
+                .iter()
+                .map(|s| EncodedSequence::encode(s).unwrap()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cluster() {
+        let counts = [
+            build_counts(&["TTGACA", "TTGACT", "TTGACA"]),
+            build_counts(&["GTTGACA", "CTTGACA"]),
+            build_counts(&["ATGTCAA", "TTGTCAA"]),
+            build_counts(&["GGGCCC", "GGGCGC"]),
+        ];
+        let motifs = counts.iter().map(|c| c.to_freq(0.1)).collect::<Vec<_>>();
+
+        let clusters = cluster(&motifs, 0.5, 4);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(
+            clusters[0]
+                .members
+                .iter()
+                .map(|m| m.index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(clusters[1].members.len(), 1);
+        assert_eq!(clusters[1].reference, 3);
+
+        // the third motif is the reverse complement of the consensus
+        let member = &clusters[0].members[2];
+        assert_eq!(member.strand, Strand::Reverse);
+
+        let archetype = clusters[0].archetype_counts(&counts);
+        assert_eq!(archetype.counts().rows(), 8);
+        let archetype = clusters[0].archetype(&motifs);
+        assert_eq!(archetype.consensus().len(), 8);
+        assert!(archetype.consensus().contains("TTGAC"));
+    }
+
+    #[test]
+    fn test_cluster_min_overlap() {
+        let counts = [
+            build_counts(&["AAAATTTT", "AAAATTTT"]),
+            build_counts(&["TTTTCCCC", "TTTTCCCC"]),
+        ];
+        let motifs = counts.iter().map(|c| c.to_freq(0.1)).collect::<Vec<_>>();
+
+        // the best alignment only overlaps the TTTT halves, but members must
+        // be aligned to the reference with the minimum overlap
+        let clusters = cluster(&motifs, -1.0, 8);
+        assert_eq!(clusters.len(), 1);
+        for member in clusters[0].members.iter() {
+            assert_eq!(member.offset, 0, "{:?}", member);
+        }
+        let clusters = cluster(&motifs, -1.0, 1);
+        assert_eq!(clusters[0].members[1].offset.abs(), 4);
+    }
+
+    #[test]
+    fn test_cluster_chain() {
+        // consecutive motifs overlap by seven columns, but the ends of the
+        // chain cannot be aligned to the middle with five columns
+        let text = "AGTTGAAGACCAATCAGCG";
+        let counts = (0..4)
+            .map(|i| build_counts(&[&text[3 * i..3 * i + 10]]))
+            .collect::<Vec<_>>();
+        let motifs = counts.iter().map(|c| c.to_freq(0.1)).collect::<Vec<_>>();
+
+        let clusters = cluster(&motifs, 0.0, 5);
+        assert_eq!(clusters.len(), 1);
+        let members = &clusters[0].members;
+        for w in members.windows(2) {
+            assert_eq!(w[1].strand, Strand::Direct, "{:?}", members);
+            assert_eq!(w[1].offset - w[0].offset, 3, "{:?}", members);
+        }
+        let archetype = clusters[0].archetype_counts(&counts);
+        assert_eq!(archetype.counts().rows(), text.len());
+    }
+
+    #[test]
+    fn test_cluster_no_overlap() {
+        let counts = [
+            build_counts(&["TTGACA", "TTGACA"]),
+            build_counts(&["TTG", "TTG"]),
+        ];
+        let motifs = counts.iter().map(|c| c.to_freq(0.1)).collect::<Vec<_>>();
+
+        // the short motif is merged by the threshold, but cannot be aligned
+        let clusters = cluster(&motifs, -1.0, 4);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[1].reference, 1);
+        assert_eq!(clusters[1].members[0].offset, 0);
+    }
+}
@@ -0,0 +1,456 @@
+//! Similarity and ungapped alignment of motifs.
+//!
+//! Motifs are compared column by column using a [`Metric`], and the best
+//! ungapped alignment is searched over every offset and on both strands,
+//! in the spirit of Tomtom. A [`Comparator`] additionally estimates the
+//! significance of an alignment score, using the distribution of column
+//! scores between the query and a set of null motifs.
+
+use std::ops::Range;
+
+use super::abc::Alphabet;
+use super::abc::ComplementableAlphabet;
+use super::dense::DenseMatrix;
+use super::pwm::known;
+use super::pwm::FrequencyMatrix;
+use super::seq::Strand;
+
+/// The minimum frequency used to avoid infinite divergences.
+const EPSILON: f32 = 1e-6;
+
+/// The number of bins used to discretize column scores.
+const BINS: usize = 100;
+
+// --- Metric ------------------------------------------------------------------
+
+/// A similarity metric between two columns of frequency matrices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    /// The Pearson correlation coefficient of the frequencies.
+    Pearson,
+    /// The negated Euclidean distance between the frequencies.
+    Euclidean,
+    /// The negated symmetric Kullback-Leibler divergence of the frequencies.
+    KullbackLeibler,
+    /// The similarity from Sandelin and Wasserman (2004), `2 - Σ(p - q)²`.
+    SandelinWasserman,
+}
+
+impl Metric {
+    /// Compute the similarity between two matrix columns.
+    ///
+    /// All metrics are oriented so that a higher value means more similar
+    /// columns, i.e. distances and divergences are negated. The wildcard
+    /// symbol is ignored, and the remaining frequencies are rescaled to sum
+    /// to one.
+    pub fn similarity<A: Alphabet>(&self, p: &[f32], q: &[f32]) -> f32 {
+        let pairs = known::<A>(p)
+            .zip(known::<A>(q))
+            .map(|((_, x), (_, y))| (x, y));
+        match self {
+            Metric::Pearson => {
+                let (mut n, mut sx, mut sy, mut sxx, mut syy, mut sxy) =
+                    (0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+                for (x, y) in pairs {
+                    n += 1.0;
+                    sx += x;
+                    sy += y;
+                    sxx += x * x;
+                    syy += y * y;
+                    sxy += x * y;
+                }
+                let cov = sxy - sx * sy / n;
+                let var = (sxx - sx * sx / n) * (syy - sy * sy / n);
+                if var > 0.0 {
+                    cov / var.sqrt()
+                } else {
+                    0.0
+                }
+            }
+            Metric::Euclidean => -pairs.map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt(),
+            Metric::KullbackLeibler => {
+                let divergence: f32 = pairs
+                    .map(|(x, y)| {
+                        let x = x.max(EPSILON);
+                        let y = y.max(EPSILON);
+                        x * (x / y).log2() + y * (y / x).log2()
+                    })
+                    .sum();
+                -0.5 * divergence
+            }
+            Metric::SandelinWasserman => 2.0 - pairs.map(|(x, y)| (x - y) * (x - y)).sum::<f32>(),
+        }
+    }
+}
+
+// --- Alignment ---------------------------------------------------------------
+
+/// An ungapped alignment between a query and a target motif.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alignment {
+    /// The offset of the query relative to the target.
+    ///
+    /// Position `i` of the query is aligned to position `i + offset` of the
+    /// target (or of its reverse-complement, depending on the strand).
+    pub offset: isize,
+    /// The strand of the target motif in the alignment.
+    pub strand: Strand,
+    /// The number of aligned columns.
+    pub overlap: usize,
+    /// The sum of the column similarities over the aligned columns.
+    pub score: f32,
+}
+
+impl Alignment {
+    /// The range of query positions covered by the alignment.
+    pub fn query_range(&self) -> Range<usize> {
+        let start = (-self.offset).max(0) as usize;
+        start..start + self.overlap
+    }
+
+    /// The range of target positions covered by the alignment.
+    ///
+    /// Positions are given on the aligned strand of the target.
+    pub fn target_range(&self) -> Range<usize> {
+        let start = self.offset.max(0) as usize;
+        start..start + self.overlap
+    }
+}
+
+/// Compute the similarity of every pair of columns of two matrices.
+///
+/// The similarity of query column `i` and target column `j` is stored at
+/// index `i * n + j`, where `n` is the length of the target.
+fn column_scores<A: Alphabet>(
+    metric: Metric,
+    query: &DenseMatrix<f32, A::K>,
+    target: &DenseMatrix<f32, A::K>,
+) -> Vec<f32> {
+    let mut scores = Vec::with_capacity(query.rows() * target.rows());
+    for p in query.iter() {
+        for q in target.iter() {
+            scores.push(metric.similarity::<A>(p, q));
+        }
+    }
+    scores
+}
+
+/// Iterate over the offsets and query ranges with at least `min_overlap` columns.
+fn offsets(m: usize, n: usize, min_overlap: usize) -> impl Iterator<Item = (isize, Range<usize>)> {
+    let min_overlap = min_overlap.max(1) as isize;
+    let (m, n) = (m as isize, n as isize);
+    (min_overlap - m..=n - min_overlap).filter_map(move |offset| {
+        let start = (-offset).max(0);
+        let end = m.min(n - offset);
+        if end - start >= min_overlap {
+            Some((offset, start as usize..end as usize))
+        } else {
+            None
+        }
+    })
+}
+
+/// Find the best ungapped alignment of a query motif against a target motif.
+///
+/// Every offset with at least `min_overlap` aligned columns is tried on
+/// both strands of the target, and the alignment with the highest sum of
+/// column similarities is returned. Returns `None` if no offset satisfies
+/// the minimum overlap.
+pub fn align<A: ComplementableAlphabet>(
+    query: &FrequencyMatrix<A>,
+    target: &FrequencyMatrix<A>,
+    metric: Metric,
+    min_overlap: usize,
+) -> Option<Alignment> {
+    let q: &DenseMatrix<f32, A::K> = query.as_ref();
+    let mut best: Option<Alignment> = None;
+    for strand in [Strand::Direct, Strand::Reverse] {
+        let rc;
+        let t: &DenseMatrix<f32, A::K> = match strand {
+            Strand::Direct => target.as_ref(),
+            Strand::Reverse => {
+                rc = target.reverse_complement();
+                rc.as_ref()
+            }
+        };
+        let scores = column_scores::<A>(metric, q, t);
+        for (offset, range) in offsets(q.rows(), t.rows(), min_overlap) {
+            let score = range
+                .clone()
+                .map(|i| scores[i * t.rows() + (i as isize + offset) as usize])
+                .sum::<f32>();
+            if best.as_ref().map(|b| score > b.score).unwrap_or(true) {
+                best = Some(Alignment {
+                    offset,
+                    strand,
+                    overlap: range.len(),
+                    score,
+                });
+            }
+        }
+    }
+    best
+}
+
+// --- Comparator --------------------------------------------------------------
+
+/// The result of a motif comparison with a [`Comparator`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// The most significant alignment of the query against the target.
+    pub alignment: Alignment,
+    /// The p-value of the alignment, corrected for the number of offsets.
+    pub pvalue: f64,
+}
+
+/// A query motif prepared for significance estimation of its alignments.
+///
+/// The null distribution of the similarity of each query column is
+/// obtained by comparing it to every column of a set of null motifs,
+/// typically the whole motif database being searched, and discretizing
+/// the resulting similarities. The null distribution of an alignment score
+/// is then computed exactly by convolution for every contiguous range of
+/// query columns, as done in Tomtom.
+#[derive(Clone, Debug)]
+pub struct Comparator<A: Alphabet> {
+    query: FrequencyMatrix<A>,
+    metric: Metric,
+    min_overlap: usize,
+    /// The lowest column similarity in the discretized range.
+    min: f32,
+    /// The number of bins per unit of similarity.
+    scale: f32,
+    /// The survival function of the discretized alignment score for every
+    /// range of query columns, indexed by range start then range length.
+    survival: Vec<Vec<Vec<f64>>>,
+}
+
+impl<A: Alphabet> Comparator<A> {
+    /// Prepare a query motif for comparison against target motifs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the null motifs contain no column at all.
+    pub fn new<I>(query: FrequencyMatrix<A>, null: I, metric: Metric, min_overlap: usize) -> Self
+    where
+        I: IntoIterator,
+        <I as IntoIterator>::Item: AsRef<FrequencyMatrix<A>>,
+    {
+        let q: &DenseMatrix<f32, A::K> = query.as_ref();
+        let m = q.rows();
+
+        // compute similarities of query columns against all null columns
+        let mut similarities = vec![Vec::new(); m];
+        for motif in null {
+            let t: &DenseMatrix<f32, A::K> = motif.as_ref().as_ref();
+            let scores = column_scores::<A>(metric, q, t);
+            for (i, s) in similarities.iter_mut().enumerate() {
+                s.extend_from_slice(&scores[i * t.rows()..(i + 1) * t.rows()]);
+            }
+        }
+        if m > 0 {
+            assert!(!similarities[0].is_empty(), "null motifs contain no column");
+        }
+
+        // compute the discretization range
+        let all = similarities.iter().flatten();
+        let min = all.clone().cloned().fold(f32::INFINITY, f32::min);
+        let max = all.cloned().fold(f32::NEG_INFINITY, f32::max);
+        let scale = if max > min {
+            (BINS - 1) as f32 / (max - min)
+        } else {
+            1.0
+        };
+
+        // build the discretized distribution of each column
+        let histograms = similarities
+            .iter()
+            .map(|s| {
+                let mut h = vec![0.0; BINS];
+                for &x in s {
+                    h[Self::discretize(min, scale, x)] += 1.0 / s.len() as f64;
+                }
+                h
+            })
+            .collect::<Vec<_>>();
+
+        // build the survival functions for every range of query columns
+        let mut survival = Vec::with_capacity(m);
+        for start in 0..m {
+            let mut pmf = vec![1.0];
+            let mut ranges = Vec::with_capacity(m - start);
+            for h in &histograms[start..] {
+                let mut next = vec![0.0; pmf.len() + BINS - 1];
+                for (x, &p) in pmf.iter().enumerate().filter(|(_, &p)| p > 0.0) {
+                    for (y, &q) in h.iter().enumerate() {
+                        next[x + y] += p * q;
+                    }
+                }
+                pmf = next;
+                let mut sf = pmf.clone();
+                for k in (0..sf.len() - 1).rev() {
+                    sf[k] += sf[k + 1];
+                }
+                ranges.push(sf);
+            }
+            survival.push(ranges);
+        }
+
+        Self {
+            query,
+            metric,
+            min_overlap,
+            min,
+            scale,
+            survival,
+        }
+    }
+
+    /// Discretize a column similarity into a bin index.
+    #[inline]
+    fn discretize(min: f32, scale: f32, x: f32) -> usize {
+        ((x - min) * scale).round().clamp(0.0, (BINS - 1) as f32) as usize
+    }
+
+    /// The query motif of the comparator.
+    pub fn query(&self) -> &FrequencyMatrix<A> {
+        &self.query
+    }
+
+    /// The metric used to compare columns.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+}
+
+impl<A: ComplementableAlphabet> Comparator<A> {
+    /// Compare the query motif to a target motif.
+    ///
+    /// The p-value of every alignment with at least the minimum overlap is
+    /// computed on both strands, and the most significant alignment is
+    /// returned. Its p-value is corrected for the number of alignments
+    /// tried, as `1 - (1 - p)^N`. Returns `None` if no offset satisfies the
+    /// minimum overlap.
+    pub fn compare(&self, target: &FrequencyMatrix<A>) -> Option<Comparison> {
+        let q: &DenseMatrix<f32, A::K> = self.query.as_ref();
+        let mut best: Option<(Alignment, f64)> = None;
+        let mut tries = 0;
+        for strand in [Strand::Direct, Strand::Reverse] {
+            let rc;
+            let t: &DenseMatrix<f32, A::K> = match strand {
+                Strand::Direct => target.as_ref(),
+                Strand::Reverse => {
+                    rc = target.reverse_complement();
+                    rc.as_ref()
+                }
+            };
+            let scores = column_scores::<A>(self.metric, q, t);
+            for (offset, range) in offsets(q.rows(), t.rows(), self.min_overlap) {
+                let mut score = 0.0;
+                let mut bins = 0;
+                for i in range.clone() {
+                    let x = scores[i * t.rows() + (i as isize + offset) as usize];
+                    score += x;
+                    bins += Self::discretize(self.min, self.scale, x);
+                }
+                let sf = &self.survival[range.start][range.len() - 1];
+                let pvalue = sf.get(bins).cloned().unwrap_or(0.0);
+                tries += 1;
+                let better = match &best {
+                    None => true,
+                    Some((b, p)) => pvalue < *p || (pvalue == *p && score > b.score),
+                };
+                if better {
+                    let alignment = Alignment {
+                        offset,
+                        strand,
+                        overlap: range.len(),
+                        score,
+                    };
+                    best = Some((alignment, pvalue));
+                }
+            }
+        }
+        best.map(|(alignment, p)| Comparison {
+            alignment,
+            pvalue: -((tries as f64) * (-p).ln_1p()).exp_m1(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::pwm::CountMatrix;
+    use crate::seq::EncodedSequence;
+
+    fn build_motif(sequences: &[&str]) -> FrequencyMatrix<Dna> {
+        CountMatrix::<Dna>::from_sequences(
+            sequences
+                .iter()
+                .map(|s| EncodedSequence::encode(s).unwrap()),
+        )
+        .unwrap()
+        .to_freq(0.25)
+    }
+
+    #[test]
+    fn test_similarity() {
+        let p = [0.7, 0.1, 0.1, 0.1, 0.0];
+        let q = [0.1, 0.1, 0.1, 0.7, 0.0];
+        for metric in [
+            Metric::Pearson,
+            Metric::Euclidean,
+            Metric::KullbackLeibler,
+            Metric::SandelinWasserman,
+        ] {
+            assert!(metric.similarity::<Dna>(&p, &p) > metric.similarity::<Dna>(&p, &q));
+        }
+        assert!((Metric::Pearson.similarity::<Dna>(&p, &p) - 1.0).abs() < 1e-5);
+        assert_eq!(Metric::Euclidean.similarity::<Dna>(&p, &p), 0.0);
+        assert_eq!(Metric::SandelinWasserman.similarity::<Dna>(&p, &p), 2.0);
+    }
+
+    #[test]
+    fn test_align() {
+        let query = build_motif(&["TTGACA", "TTGACT"]);
+        let target = build_motif(&["GCTTGACAGC", "GCTTGACTGC"]);
+
+        let aln = align(&query, &target, Metric::Pearson, 4).unwrap();
+        assert_eq!(aln.offset, 2);
+        assert_eq!(aln.strand, Strand::Direct);
+        assert_eq!(aln.overlap, 6);
+        assert_eq!(aln.query_range(), 0..6);
+        assert_eq!(aln.target_range(), 2..8);
+
+        let aln = align(&query, &target.reverse_complement(), Metric::Pearson, 4).unwrap();
+        assert_eq!(aln.offset, 2);
+        assert_eq!(aln.strand, Strand::Reverse);
+        assert_eq!(aln.overlap, 6);
+
+        assert!(align(&query, &target, Metric::Pearson, 7).is_none());
+    }
+
+    #[test]
+    fn test_comparator() {
+        let query = build_motif(&["TTGACA", "TTGACT"]);
+        let related = build_motif(&["GCTTGACAGC", "GCTTGACTGC"]);
+        let unrelated = build_motif(&["CCCCGG", "CCCGGG"]);
+        let null = [
+            related.clone(),
+            unrelated.clone(),
+            build_motif(&["ATATATAT", "ATATATAT"]),
+            build_motif(&["GCGCAAGT", "GCTCATGT"]),
+        ];
+
+        let comparator = Comparator::new(query, &null, Metric::Pearson, 4);
+        let hit = comparator.compare(&related).unwrap();
+        assert_eq!(hit.alignment.offset, 2);
+        assert_eq!(hit.alignment.strand, Strand::Direct);
+        let miss = comparator.compare(&unrelated).unwrap();
+        assert!(hit.pvalue < miss.pvalue);
+        assert!((0.0..=1.0).contains(&miss.pvalue));
+    }
+}
@@ -0,0 +1,312 @@
+//! Detection of cis-regulatory modules as clusters of motif sites.
+//!
+//! Single motif hits are noisy, but regulatory regions such as enhancers
+//! often contain several sites, of the same motif or of different motifs,
+//! close to each other. In the spirit of Cluster-Buster (Frith et al., 2003),
+//! the [`Detector`] collects the hits of several motifs along a sequence,
+//! and chains non-overlapping hits into modules. A module is scored by the
+//! sum of the scores of its sites, minus a penalty for every position
+//! between two consecutive sites, and the highest-scoring chains are found
+//! with dynamic programming.
+
+use super::abc::Alphabet;
+use super::abc::ComplementableAlphabet;
+use super::num::StrictlyPositive;
+use super::pli::Score;
+use super::pli::Threshold;
+use super::pwm::ScoringMatrix;
+use super::seq::reverse_strand;
+use super::seq::EncodedSequence;
+use super::seq::ReverseStrand;
+use super::seq::Strand;
+
+// --- Module ------------------------------------------------------------------
+
+/// A motif site contributing to a module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Site {
+    /// The index of the motif.
+    pub motif: usize,
+    /// The start of the site on the direct strand.
+    pub position: usize,
+    /// The length of the site.
+    pub length: usize,
+    /// The strand of the site.
+    pub strand: Strand,
+    /// The score of the site.
+    pub score: f32,
+}
+
+impl Site {
+    /// The end of the site on the direct strand.
+    pub fn end(&self) -> usize {
+        self.position + self.length
+    }
+}
+
+/// A cluster of motif sites.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Module {
+    /// The start of the first site of the module.
+    pub start: usize,
+    /// The end of the last site of the module.
+    pub end: usize,
+    /// The score of the module.
+    pub score: f32,
+    /// The sites of the module, sorted by position.
+    pub sites: Vec<Site>,
+}
+
+// --- Detector ----------------------------------------------------------------
+
+/// A Cluster-Buster-like detector of cis-regulatory modules.
+#[derive(Clone, Debug)]
+pub struct Detector<A: Alphabet> {
+    site_threshold: f32,
+    gap_penalty: f32,
+    module_threshold: f32,
+    min_sites: usize,
+    reverse: Option<ReverseStrand<A>>,
+}
+
+impl<A: Alphabet> Default for Detector<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Alphabet> Detector<A> {
+    /// Create a new detector with default parameters.
+    ///
+    /// By default, sites must score at least 6 bits, every position between
+    /// two sites costs 0.1 bits, and modules must have at least 2 sites and
+    /// score at least 12 bits.
+    pub fn new() -> Self {
+        Self {
+            site_threshold: 6.0,
+            gap_penalty: 0.1,
+            module_threshold: 12.0,
+            min_sites: 2,
+            reverse: None,
+        }
+    }
+
+    /// Use the given minimum score for a hit to be considered as a site.
+    pub fn with_site_threshold(mut self, site_threshold: f32) -> Self {
+        self.site_threshold = site_threshold;
+        self
+    }
+
+    /// Use the given penalty for every position between two sites.
+    pub fn with_gap_penalty(mut self, gap_penalty: f32) -> Self {
+        self.gap_penalty = gap_penalty;
+        self
+    }
+
+    /// Use the given minimum score for a cluster to be reported as a module.
+    pub fn with_module_threshold(mut self, module_threshold: f32) -> Self {
+        self.module_threshold = module_threshold;
+        self
+    }
+
+    /// Use the given minimum number of sites for a cluster to be reported.
+    pub fn with_min_sites(mut self, min_sites: usize) -> Self {
+        self.min_sites = min_sites;
+        self
+    }
+
+    /// Collect the sites of all motifs in the sequence.
+    fn sites<C, P>(
+        &self,
+        pipeline: &P,
+        motifs: &[ScoringMatrix<A>],
+        sequence: &EncodedSequence<A>,
+    ) -> Vec<Site>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + Threshold<C>,
+    {
+        let wrap = motifs.iter().map(|m| m.len()).max().unwrap_or(1) - 1;
+        let mut strands = vec![(Strand::Direct, sequence.to_striped::<C>())];
+        if let Some(f) = self.reverse {
+            strands.push((Strand::Reverse, f(sequence).to_striped::<C>()));
+        }
+        for (_, striped) in strands.iter_mut() {
+            striped.configure_wrap(wrap);
+        }
+
+        let mut sites = Vec::new();
+        for (motif, pssm) in motifs.iter().enumerate() {
+            if pssm.len() > sequence.len() {
+                continue;
+            }
+            let last = sequence.len() - pssm.len();
+            for (strand, striped) in strands.iter() {
+                let scores = pipeline.score(striped, pssm);
+                for i in pipeline.threshold(&scores, self.site_threshold) {
+                    sites.push(Site {
+                        motif,
+                        position: match strand {
+                            Strand::Direct => i,
+                            Strand::Reverse => last - i,
+                        },
+                        length: pssm.len(),
+                        strand: *strand,
+                        score: scores[i],
+                    });
+                }
+            }
+        }
+        sites
+    }
+
+    /// Detect the modules of the given motifs in a sequence.
+    ///
+    /// Modules are found greedily: the highest-scoring chain of sites is
+    /// reported first, then the next highest-scoring chain that does not
+    /// overlap an already reported module, until no chain scores above the
+    /// module threshold. Modules are returned sorted by position.
+    pub fn detect<C, P>(
+        &self,
+        pipeline: &P,
+        motifs: &[ScoringMatrix<A>],
+        sequence: &EncodedSequence<A>,
+    ) -> Vec<Module>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + Threshold<C>,
+    {
+        let mut sites = self.sites(pipeline, motifs, sequence);
+        sites.sort_by_key(|s| (s.position, s.end(), s.motif));
+
+        // best chain ending with each site: the penalty being linear, the
+        // best predecessor maximizes `chain[j] + penalty * end[j]` among the
+        // sites ending before the start of the current site
+        let mut by_end = (0..sites.len()).collect::<Vec<usize>>();
+        by_end.sort_by_key(|&j| sites[j].end());
+        let mut chain = vec![0.0f32; sites.len()];
+        let mut previous = vec![None; sites.len()];
+        let mut best: Option<(f32, usize)> = None;
+        let mut k = 0;
+        for i in 0..sites.len() {
+            while k < by_end.len() && sites[by_end[k]].end() <= sites[i].position {
+                let j = by_end[k];
+                let value = chain[j] + self.gap_penalty * sites[j].end() as f32;
+                if best.map(|b| value > b.0).unwrap_or(true) {
+                    best = Some((value, j));
+                }
+                k += 1;
+            }
+            chain[i] = sites[i].score;
+            if let Some((value, j)) = best {
+                let extended = value - self.gap_penalty * sites[i].position as f32;
+                if extended > 0.0 {
+                    chain[i] += extended;
+                    previous[i] = Some(j);
+                }
+            }
+        }
+
+        // report non-overlapping chains by decreasing score
+        let mut ends = (0..sites.len()).collect::<Vec<usize>>();
+        ends.sort_by(|&i, &j| chain[j].total_cmp(&chain[i]).then(i.cmp(&j)));
+        let mut modules: Vec<Module> = Vec::new();
+        for i in ends {
+            if chain[i] < self.module_threshold {
+                break;
+            }
+            let mut members = vec![i];
+            while let Some(j) = previous[*members.last().unwrap()] {
+                members.push(j);
+            }
+            if members.len() < self.min_sites {
+                continue;
+            }
+            members.reverse();
+            let start = sites[members[0]].position;
+            let end = sites[i].end();
+            if modules.iter().any(|m| m.start < end && start < m.end) {
+                continue;
+            }
+            modules.push(Module {
+                start,
+                end,
+                score: chain[i],
+                sites: members.into_iter().map(|j| sites[j].clone()).collect(),
+            });
+        }
+        modules.sort_by_key(|m| m.start);
+        modules
+    }
+}
+
+impl<A: ComplementableAlphabet> Detector<A> {
+    /// Collect sites on both strands, or only on the direct strand.
+    pub fn with_both_strands(mut self, both_strands: bool) -> Self {
+        self.reverse = reverse_strand(both_strands);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use typenum::consts::U32;
+
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::pli::Pipeline;
+    use crate::pwm::CountMatrix;
+
+    fn motif(site: &str) -> ScoringMatrix<Dna> {
+        CountMatrix::from_sequences([EncodedSequence::encode(site).unwrap()])
+            .unwrap()
+            .to_freq(0.25)
+            .to_scoring(None)
+    }
+
+    #[test]
+    fn test_detect() {
+        let motifs = [motif("TTGACGCA"), motif("GATTACA")];
+        let mut sequence = "C".repeat(200);
+        sequence.replace_range(20..28, "TTGACGCA");
+        sequence.replace_range(35..42, "GATTACA");
+        sequence.replace_range(50..58, "TGCGTCAA");
+        sequence.replace_range(185..193, "TTGACGCA");
+        let sequence = EncodedSequence::encode(&sequence).unwrap();
+
+        let pli = Pipeline::<Dna, _>::generic();
+        let detector = Detector::new().with_site_threshold(9.0);
+
+        // direct strand only: the isolated site is not a module
+        let modules = detector.detect::<U32, _>(&pli, &motifs, &sequence);
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].start, 20);
+        assert_eq!(modules[0].end, 42);
+        assert_eq!(modules[0].sites.len(), 2);
+        assert_eq!(modules[0].sites[0].motif, 0);
+        assert_eq!(modules[0].sites[1].motif, 1);
+        let expected = modules[0].sites[0].score + modules[0].sites[1].score - 0.1 * 7.0;
+        assert!((modules[0].score - expected).abs() < 1e-4);
+
+        // both strands: the reverse site extends the module
+        let detector = detector.with_both_strands(true);
+        let modules = detector.detect::<U32, _>(&pli, &motifs, &sequence);
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].end, 58);
+        assert_eq!(modules[0].sites.len(), 3);
+        assert_eq!(modules[0].sites[2].strand, Strand::Reverse);
+        assert_eq!(modules[0].sites[2].position, 50);
+
+        // a large gap penalty splits the sites
+        let detector = detector.with_gap_penalty(10.0);
+        assert!(detector
+            .detect::<U32, _>(&pli, &motifs, &sequence)
+            .is_empty());
+
+        // a single site can form a module
+        let detector = detector.with_min_sites(1).with_module_threshold(9.0);
+        let modules = detector.detect::<U32, _>(&pli, &motifs, &sequence);
+        assert_eq!(modules.len(), 4);
+    }
+}
@@ -302,6 +302,9 @@ macro_rules! iterator {
             fn next(&mut self) -> Option<Self::Item> {
                 self.indices.next().map(|i| self.get(i))
             }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.indices.size_hint()
+            }
         }
 
         impl<'a, $T, C, A> ExactSizeIterator for $t<'a, $T, C, A>
@@ -0,0 +1,1246 @@
+//! De novo motif discovery.
+//!
+//! The [`Em`] algorithm follows MEME (Bailey & Elkan, 1994): a motif of a
+//! fixed width is initialized from an enriched k-mer, and refined by
+//! alternating an E-step, which computes the posterior probability of a
+//! site starting at every position of the sequences, and an M-step, which
+//! re-estimates the motif frequencies from the expected site counts. The
+//! E-step scores every position with the motif log-odds, and is computed
+//! with a [`Pipeline`](crate::pli::Pipeline), so it benefits from the
+//! SIMD implementations of [`Score`].
+//!
+//! The [`Discriminative`] algorithm follows STREME (Bailey, 2021) and DREME
+//! (Bailey, 2011): instead of modeling sites against a background model,
+//! it searches for motifs enriched in a set of primary sequences compared
+//! to a set of control sequences, and reports several motifs by masking the
+//! sites of each motif once it has been found.
+
+use std::collections::HashMap;
+
+use super::abc::Alphabet;
+use super::abc::Background;
+use super::abc::ComplementableAlphabet;
+use super::abc::Dna;
+use super::abc::Nucleotide;
+use super::abc::Symbol;
+use super::dense::DenseMatrix;
+use super::num::StrictlyPositive;
+use super::pli::BestPosition;
+use super::pli::Score;
+use super::pli::Threshold;
+use super::prior::BackgroundPrior;
+use super::pwm::CountMatrix;
+use super::pwm::FrequencyMatrix;
+use super::pwm::ScoringMatrix;
+use super::seq::reverse_strand;
+use super::seq::EncodedSequence;
+use super::seq::Strand;
+use super::seq::Strands;
+use super::seq::StripedSequence;
+use super::stats::binomial_test;
+use super::stats::fisher_exact;
+
+/// The largest log-odds score used when computing likelihood ratios.
+const MAX_SCORE: f64 = 1000.0;
+
+// --- Model -------------------------------------------------------------------
+
+/// The distribution of the sites in the sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    /// One Occurrence Per Sequence: every sequence contains exactly one site.
+    Oops,
+    /// Zero or One Occurrence Per Sequence: every sequence contains at most
+    /// one site.
+    Zoops,
+    /// Any Number of Repetitions: every position may start a site.
+    Anr,
+}
+
+// --- Motif -------------------------------------------------------------------
+
+/// A site of a discovered motif.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Site {
+    /// The index of the sequence containing the site.
+    pub sequence: usize,
+    /// The position of the site in the sequence.
+    pub position: usize,
+    /// The log-odds score of the site with the final motif, in bits.
+    pub score: f32,
+    /// The posterior probability of the site under the final model.
+    pub probability: f64,
+}
+
+/// A motif discovered de novo.
+#[derive(Clone, Debug)]
+pub struct Motif<A: Alphabet> {
+    /// The counts of the symbols in the predicted sites.
+    pub counts: CountMatrix<A>,
+    /// The frequencies estimated by the last M-step.
+    pub frequencies: FrequencyMatrix<A>,
+    /// The predicted sites.
+    pub sites: Vec<Site>,
+    /// The log-likelihood ratio of the model against the background, in nats.
+    pub log_likelihood: f64,
+    /// The number of EM iterations that were run.
+    pub iterations: usize,
+}
+
+// --- Em ----------------------------------------------------------------------
+
+/// The state of the EM algorithm between two iterations.
+struct State<A: Alphabet> {
+    frequencies: FrequencyMatrix<A>,
+    /// The prior probability of a sequence containing a site (ZOOPS), or of
+    /// a position starting a site (ANR).
+    prior: f64,
+    log_likelihood: f64,
+    iterations: usize,
+}
+
+/// A MEME-like motif discovery algorithm.
+#[derive(Clone, Debug)]
+pub struct Em<A: Alphabet> {
+    width: usize,
+    model: Model,
+    background: Background<A>,
+    pseudocount: f32,
+    max_iterations: usize,
+    tolerance: f32,
+    seeds: usize,
+}
+
+impl<A: Alphabet> Em<A> {
+    /// Create a new EM motif finder for motifs of the given width.
+    ///
+    /// By default, the OOPS model is used with a uniform background, a
+    /// total pseudocount of 0.1, and the 5 most enriched k-mers as seeds.
+    ///
+    /// # Panics
+    /// Panics if `width` is zero.
+    pub fn new(width: usize) -> Self {
+        assert!(width > 0, "cannot discover motifs of width 0");
+        Self {
+            width,
+            model: Model::Oops,
+            background: Background::default(),
+            pseudocount: 0.1,
+            max_iterations: 50,
+            tolerance: 1e-3,
+            seeds: 5,
+        }
+    }
+
+    /// Use the given model for the distribution of sites.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Use the given background frequencies.
+    pub fn with_background<B>(mut self, background: B) -> Self
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        self.background = background.into().unwrap_or_default();
+        self
+    }
+
+    /// Use the given total pseudocount in the M-step.
+    pub fn with_pseudocount(mut self, pseudocount: f32) -> Self {
+        self.pseudocount = pseudocount;
+        self
+    }
+
+    /// Stop after the given number of iterations.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Stop when no frequency changes by more than the given tolerance.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Run EM from the given number of most enriched k-mers.
+    pub fn with_seeds(mut self, seeds: usize) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Find the most enriched k-mers to use as seeds.
+    ///
+    /// K-mers are ranked by the ratio of the number of sequences where they
+    /// occur to the number of sequences expected under the background.
+    fn seed_kmers(&self, sequences: &[EncodedSequence<A>]) -> Vec<Vec<usize>> {
+        let wildcard = A::default_symbol().as_index();
+        let mut occurrences: HashMap<Vec<usize>, usize> = HashMap::new();
+        for seq in sequences.iter().filter(|s| s.len() >= self.width) {
+            let mut kmers = seq[..]
+                .windows(self.width)
+                .map(|w| w.iter().map(|s| s.as_index()).collect::<Vec<_>>())
+                .filter(|k| !k.contains(&wildcard))
+                .collect::<Vec<_>>();
+            kmers.sort_unstable();
+            kmers.dedup();
+            for kmer in kmers {
+                *occurrences.entry(kmer).or_default() += 1;
+            }
+        }
+
+        let bg = self.background.frequencies();
+        let mut ranked = occurrences
+            .into_iter()
+            .map(|(kmer, n)| {
+                let p: f64 = kmer.iter().map(|&s| bg[s] as f64).product();
+                let expected: f64 = sequences
+                    .iter()
+                    .filter(|s| s.len() >= self.width)
+                    .map(|s| (p * (s.len() - self.width + 1) as f64).min(1.0))
+                    .sum();
+                (n as f64 / expected, n, kmer)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|x, y| y.0.total_cmp(&x.0).then(y.1.cmp(&x.1)).then(x.2.cmp(&y.2)));
+        ranked
+            .into_iter()
+            .take(self.seeds)
+            .map(|(_, _, kmer)| kmer)
+            .collect()
+    }
+
+    /// Build the initial frequencies from a seed k-mer.
+    fn seed_frequencies(&self, kmer: &[usize]) -> FrequencyMatrix<A> {
+        let wildcard = A::default_symbol().as_index();
+        let s = A::symbols().len() - 1;
+        let mut data = DenseMatrix::<f32, A::K>::new(kmer.len());
+        for (row, &x) in data.iter_mut().zip(kmer) {
+            for (j, f) in row.iter_mut().enumerate() {
+                *f = match j {
+                    _ if j == wildcard => 0.0,
+                    _ if j == x => 0.5,
+                    _ => 0.5 / (s - 1) as f32,
+                };
+            }
+        }
+        FrequencyMatrix::new_unchecked(data)
+    }
+
+    /// Compute the likelihood ratios of sites starting at every position.
+    fn ratios<C, P>(
+        &self,
+        pipeline: &P,
+        striped: &[StripedSequence<A, C>],
+        pssm: &ScoringMatrix<A>,
+    ) -> Vec<Vec<f64>>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C>,
+    {
+        striped
+            .iter()
+            .map(|seq| {
+                pipeline
+                    .score(seq, pssm)
+                    .iter()
+                    .map(|&s| (s as f64).min(MAX_SCORE).exp2())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Compute the posterior probabilities of sites and the log-likelihood.
+    fn posteriors(&self, ratios: &[Vec<f64>], prior: f64) -> (Vec<Vec<f64>>, f64) {
+        let mut log_likelihood = 0.0;
+        let posteriors = ratios
+            .iter()
+            .map(|r| {
+                let m = r.len() as f64;
+                let total: f64 = r.iter().sum();
+                match self.model {
+                    Model::Oops => {
+                        log_likelihood += (total / m).ln();
+                        r.iter().map(|x| x / total).collect()
+                    }
+                    Model::Zoops => {
+                        let lambda = prior / m;
+                        let z = (1.0 - prior) + lambda * total;
+                        log_likelihood += z.ln();
+                        r.iter().map(|x| lambda * x / z).collect()
+                    }
+                    Model::Anr => r
+                        .iter()
+                        .map(|x| {
+                            let z = prior * x + (1.0 - prior);
+                            log_likelihood += z.ln();
+                            prior * x / z
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        (posteriors, log_likelihood)
+    }
+
+    /// Re-estimate the motif frequencies from the expected site counts.
+    fn maximization(
+        &self,
+        sequences: &[&EncodedSequence<A>],
+        posteriors: &[Vec<f64>],
+    ) -> FrequencyMatrix<A> {
+        let mut counts = DenseMatrix::<f32, A::K>::new(self.width);
+        for (seq, z) in sequences.iter().zip(posteriors) {
+            for (j, &p) in z.iter().enumerate() {
+                if p > 0.0 {
+                    for (k, s) in seq[j..j + self.width].iter().enumerate() {
+                        counts[k][s.as_index()] += p as f32;
+                    }
+                }
+            }
+        }
+        let prior = BackgroundPrior::new(self.pseudocount, self.background.clone());
+        CountMatrix::<A, f32>::new(counts).unwrap().to_freq(prior)
+    }
+
+    /// Run EM from an initial motif until convergence.
+    fn refine<C, P>(
+        &self,
+        pipeline: &P,
+        sequences: &[&EncodedSequence<A>],
+        striped: &[StripedSequence<A, C>],
+        initial: FrequencyMatrix<A>,
+    ) -> State<A>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C>,
+    {
+        let positions: usize = sequences.iter().map(|s| s.len() - self.width + 1).sum();
+        let mut state = State {
+            frequencies: initial,
+            prior: match self.model {
+                Model::Oops => 1.0,
+                Model::Zoops => 0.5,
+                Model::Anr => sequences.len() as f64 / positions as f64,
+            },
+            log_likelihood: f64::NEG_INFINITY,
+            iterations: 0,
+        };
+        while state.iterations < self.max_iterations {
+            let pssm = state.frequencies.to_scoring(self.background.clone());
+            let ratios = self.ratios(pipeline, striped, &pssm);
+            let (posteriors, log_likelihood) = self.posteriors(&ratios, state.prior);
+            let frequencies = self.maximization(sequences, &posteriors);
+
+            let expected: f64 = posteriors.iter().flatten().sum();
+            state.prior = match self.model {
+                Model::Oops => 1.0,
+                Model::Zoops => (expected / sequences.len() as f64).clamp(1e-6, 1.0 - 1e-6),
+                Model::Anr => (expected / positions as f64).clamp(1e-9, 0.5),
+            };
+            state.log_likelihood = log_likelihood;
+            state.iterations += 1;
+
+            let old: &DenseMatrix<f32, A::K> = state.frequencies.as_ref();
+            let new: &DenseMatrix<f32, A::K> = frequencies.as_ref();
+            let delta = old
+                .iter()
+                .zip(new.iter())
+                .flat_map(|(x, y)| x.iter().zip(y.iter()).map(|(a, b)| (a - b).abs()))
+                .fold(0.0, f32::max);
+            state.frequencies = frequencies;
+            if delta < self.tolerance {
+                break;
+            }
+        }
+        state
+    }
+
+    /// Discover a motif in the given sequences.
+    ///
+    /// EM is run from each seed k-mer, and the motif with the highest
+    /// log-likelihood ratio is returned, along with the sites predicted
+    /// with the final model: the best site of each sequence for OOPS, the
+    /// best site of each sequence with a posterior probability of containing
+    /// a site of at least 0.5 for ZOOPS, and all non-overlapping positions
+    /// with a posterior probability of at least 0.5 for ANR. Sequences
+    /// shorter than the motif width are ignored.
+    ///
+    /// Returns `None` if no seed could be found, or if no site was predicted.
+    pub fn run<C, P>(&self, pipeline: &P, sequences: &[EncodedSequence<A>]) -> Option<Motif<A>>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + BestPosition<C> + Threshold<C>,
+    {
+        let indices = (0..sequences.len())
+            .filter(|&i| sequences[i].len() >= self.width)
+            .collect::<Vec<usize>>();
+        let usable = indices.iter().map(|&i| &sequences[i]).collect::<Vec<_>>();
+        let striped = usable
+            .iter()
+            .map(|seq| {
+                let mut striped = seq.to_striped::<C>();
+                striped.configure_wrap(self.width - 1);
+                striped
+            })
+            .collect::<Vec<_>>();
+
+        let state = self
+            .seed_kmers(sequences)
+            .iter()
+            .map(|kmer| self.refine(pipeline, &usable, &striped, self.seed_frequencies(kmer)))
+            // ignore runs diverging to NaN, e.g. after an overflow of ratios
+            .filter(|state| !state.log_likelihood.is_nan())
+            .max_by(|x, y| x.log_likelihood.total_cmp(&y.log_likelihood))?;
+
+        // predict sites with the final model
+        let pssm = state.frequencies.to_scoring(self.background.clone());
+        let mut sites = Vec::new();
+        for (k, seq) in striped.iter().enumerate() {
+            let scores = pipeline.score(seq, &pssm);
+            let ratios = scores
+                .iter()
+                .map(|&s| (s as f64).min(MAX_SCORE).exp2())
+                .collect::<Vec<_>>();
+            let total: f64 = ratios.iter().sum();
+            let m = ratios.len() as f64;
+            match self.model {
+                Model::Oops | Model::Zoops => {
+                    let position = pipeline.best_position(&scores)?;
+                    let (probability, has_site) = match self.model {
+                        Model::Oops => (ratios[position] / total, 1.0),
+                        _ => {
+                            let lambda = state.prior / m;
+                            let z = (1.0 - state.prior) + lambda * total;
+                            (lambda * ratios[position] / z, lambda * total / z)
+                        }
+                    };
+                    if has_site >= 0.5 {
+                        sites.push(Site {
+                            sequence: indices[k],
+                            position,
+                            score: scores[position],
+                            probability,
+                        });
+                    }
+                }
+                Model::Anr => {
+                    let threshold = ((1.0 - state.prior) / state.prior).log2() as f32;
+                    let mut candidates = pipeline.threshold(&scores, threshold);
+                    candidates.sort_by(|&i, &j| scores[j].total_cmp(&scores[i]));
+                    let mut taken: Vec<usize> = Vec::new();
+                    for position in candidates {
+                        if taken.iter().all(|&p| p.abs_diff(position) >= self.width) {
+                            taken.push(position);
+                        }
+                    }
+                    taken.sort_unstable();
+                    for position in taken {
+                        let x = state.prior * ratios[position];
+                        sites.push(Site {
+                            sequence: indices[k],
+                            position,
+                            score: scores[position],
+                            probability: x / (x + 1.0 - state.prior),
+                        });
+                    }
+                }
+            }
+        }
+        if sites.is_empty() {
+            return None;
+        }
+
+        let counts = CountMatrix::from_sequences(
+            sites
+                .iter()
+                .map(|s| sequences[s.sequence].slice(s.position..s.position + self.width)),
+        )
+        .unwrap();
+        Some(Motif {
+            counts,
+            frequencies: state.frequencies,
+            sites,
+            log_likelihood: state.log_likelihood,
+            iterations: state.iterations,
+        })
+    }
+}
+
+// --- Discriminative ----------------------------------------------------------
+
+/// The test used to score the enrichment of a motif against control sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Statistic {
+    /// Fisher's exact test on the number of primary and control sequences
+    /// with a site, as done in DREME.
+    Fisher,
+    /// Binomial test on the fraction of primary sequences among sequences
+    /// with a site, as done in STREME.
+    Binomial,
+}
+
+/// An occurrence of a discriminative motif.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    /// The index of the sequence containing the site.
+    pub sequence: usize,
+    /// The position of the site, relative to the start of the direct strand.
+    pub position: usize,
+    /// The strand of the site.
+    pub strand: Strand,
+    /// The log-odds score of the site, in bits.
+    pub score: f32,
+}
+
+/// A motif enriched in primary sequences compared to control sequences.
+#[derive(Clone, Debug)]
+pub struct DiscriminativeMotif {
+    /// The IUPAC word the motif was refined from.
+    pub word: String,
+    /// The counts of the symbols in the primary sites.
+    pub counts: CountMatrix<Dna>,
+    /// The score threshold maximizing the enrichment of the motif.
+    pub threshold: f32,
+    /// The number of primary sequences with a site.
+    pub primary: usize,
+    /// The number of control sequences with a site.
+    pub control: usize,
+    /// The natural logarithm of the enrichment p-value.
+    pub log_pvalue: f64,
+    /// The best site of every primary sequence scoring above the threshold.
+    pub sites: Vec<Hit>,
+}
+
+/// A degenerate word, storing the allowed symbols of each position as a bitmask.
+type Word = Vec<u8>;
+
+/// Check whether a window of a sequence matches a degenerate word.
+fn matches(word: &[u8], window: &[Nucleotide]) -> bool {
+    word.iter()
+        .zip(window)
+        .all(|(&mask, s)| mask & (1 << s.as_index()) != 0)
+}
+
+/// Get the reverse complement of a degenerate word.
+fn reverse_complement(word: &[u8]) -> Word {
+    word.iter()
+        .rev()
+        .map(|&mask| {
+            Dna::symbols()
+                .iter()
+                .filter(|s| mask & (1 << s.as_index()) != 0)
+                .fold(0, |m, &s| m | (1 << Dna::complement(s).as_index()))
+        })
+        .collect()
+}
+
+/// Get the IUPAC representation of a degenerate word.
+fn iupac(word: &[u8]) -> String {
+    word.iter()
+        .map(|&mask| {
+            let has = |n: Nucleotide| mask & (1 << n.as_index()) != 0;
+            match (
+                has(Nucleotide::A),
+                has(Nucleotide::C),
+                has(Nucleotide::G),
+                has(Nucleotide::T),
+            ) {
+                (true, false, false, false) => 'A',
+                (false, true, false, false) => 'C',
+                (false, false, true, false) => 'G',
+                (false, false, false, true) => 'T',
+                (true, true, false, false) => 'M',
+                (true, false, true, false) => 'R',
+                (true, false, false, true) => 'W',
+                (false, true, true, false) => 'S',
+                (false, true, false, true) => 'Y',
+                (false, false, true, true) => 'K',
+                (true, true, true, false) => 'V',
+                (true, true, false, true) => 'H',
+                (true, false, true, true) => 'D',
+                (false, true, true, true) => 'B',
+                _ => 'N',
+            }
+        })
+        .collect()
+}
+
+/// A STREME-like discriminative motif discovery algorithm.
+///
+/// Motifs are seeded from the exact k-mers most enriched in the primary
+/// sequences compared to the control sequences, generalized into IUPAC
+/// words by adding symbols to their positions while this improves the
+/// enrichment, and refined into count matrices by rescanning the sequences
+/// with a [`Pipeline`](crate::pli::Pipeline). After each motif is found,
+/// its sites are masked in both sets of sequences so that the next motif
+/// explains different sites.
+#[derive(Clone, Debug)]
+pub struct Discriminative {
+    width: usize,
+    statistic: Statistic,
+    both_strands: bool,
+    background: Background<Dna>,
+    pseudocount: f32,
+    seeds: usize,
+    max_degenerate: usize,
+    refinements: usize,
+    max_motifs: usize,
+    pvalue: f64,
+}
+
+impl Discriminative {
+    /// Create a new discriminative motif finder for motifs of the given width.
+    ///
+    /// By default, enrichment is scored with Fisher's exact test on both
+    /// strands, up to 5 motifs with a p-value of at most 0.05 are reported,
+    /// and each is refined from the best of 10 seeds with at most 2
+    /// degenerate positions.
+    ///
+    /// # Panics
+    /// Panics if `width` is zero.
+    pub fn new(width: usize) -> Self {
+        assert!(width > 0, "cannot discover motifs of width 0");
+        Self {
+            width,
+            statistic: Statistic::Fisher,
+            both_strands: true,
+            background: Background::default(),
+            pseudocount: 0.1,
+            seeds: 10,
+            max_degenerate: 2,
+            refinements: 5,
+            max_motifs: 5,
+            pvalue: 0.05,
+        }
+    }
+
+    /// Use the given test to score enrichment.
+    pub fn with_statistic(mut self, statistic: Statistic) -> Self {
+        self.statistic = statistic;
+        self
+    }
+
+    /// Search sites on both strands, or only on the direct strand.
+    pub fn with_both_strands(mut self, both_strands: bool) -> Self {
+        self.both_strands = both_strands;
+        self
+    }
+
+    /// Use the given background frequencies to build scoring matrices.
+    pub fn with_background<B>(mut self, background: B) -> Self
+    where
+        B: Into<Option<Background<Dna>>>,
+    {
+        self.background = background.into().unwrap_or_default();
+        self
+    }
+
+    /// Use the given total pseudocount to build scoring matrices.
+    pub fn with_pseudocount(mut self, pseudocount: f32) -> Self {
+        self.pseudocount = pseudocount;
+        self
+    }
+
+    /// Refine the given number of most enriched k-mers for each motif.
+    pub fn with_seeds(mut self, seeds: usize) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Allow at most the given number of degenerate positions in words.
+    pub fn with_max_degenerate(mut self, max_degenerate: usize) -> Self {
+        self.max_degenerate = max_degenerate;
+        self
+    }
+
+    /// Rescan the sequences at most the given number of times per motif.
+    pub fn with_refinements(mut self, refinements: usize) -> Self {
+        self.refinements = refinements;
+        self
+    }
+
+    /// Report at most the given number of motifs.
+    pub fn with_max_motifs(mut self, max_motifs: usize) -> Self {
+        self.max_motifs = max_motifs;
+        self
+    }
+
+    /// Stop when the best motif has a p-value above the given threshold.
+    pub fn with_pvalue(mut self, pvalue: f64) -> Self {
+        self.pvalue = pvalue;
+        self
+    }
+
+    /// Compute the enrichment of a feature found in `a` of `n` primary
+    /// sequences, and in `c` of `m` control sequences.
+    fn log_pvalue(&self, a: usize, n: usize, c: usize, m: usize) -> f64 {
+        match self.statistic {
+            Statistic::Fisher => fisher_exact(a, n - a, c, m - c),
+            Statistic::Binomial => binomial_test(a, a + c, n as f64 / (n + m) as f64),
+        }
+    }
+
+    /// Count the sequences containing a match to a word.
+    fn count(&self, word: &[u8], sequences: &[EncodedSequence<Dna>]) -> usize {
+        let rc = reverse_complement(word);
+        sequences
+            .iter()
+            .filter(|seq| {
+                seq[..].windows(self.width).any(|window| {
+                    matches(word, window) || (self.both_strands && matches(&rc, window))
+                })
+            })
+            .count()
+    }
+
+    /// Compute the enrichment of a word.
+    fn word_pvalue(
+        &self,
+        word: &[u8],
+        primary: &[EncodedSequence<Dna>],
+        control: &[EncodedSequence<Dna>],
+    ) -> f64 {
+        let a = self.count(word, primary);
+        let c = self.count(word, control);
+        self.log_pvalue(a, primary.len(), c, control.len())
+    }
+
+    /// Find the exact k-mers most enriched in the primary sequences.
+    fn seed_words(
+        &self,
+        primary: &[EncodedSequence<Dna>],
+        control: &[EncodedSequence<Dna>],
+    ) -> Vec<Word> {
+        let wildcard = Dna::default_symbol().as_index() as u8;
+        let mut occurrences: HashMap<Word, (usize, usize)> = HashMap::new();
+        for (k, sequences) in [primary, control].into_iter().enumerate() {
+            for seq in sequences {
+                let mut kmers = seq[..]
+                    .windows(self.width)
+                    .map(|w| w.iter().map(|s| s.as_index() as u8).collect::<Word>())
+                    .filter(|kmer| !kmer.contains(&wildcard))
+                    .map(|kmer| {
+                        let rc = kmer
+                            .iter()
+                            .rev()
+                            .map(|&x| Dna::complement(Dna::symbols()[x as usize]).as_index() as u8)
+                            .collect::<Word>();
+                        if self.both_strands && rc < kmer {
+                            rc
+                        } else {
+                            kmer
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                kmers.sort_unstable();
+                kmers.dedup();
+                for kmer in kmers {
+                    let entry = occurrences.entry(kmer).or_default();
+                    if k == 0 {
+                        entry.0 += 1;
+                    } else {
+                        entry.1 += 1;
+                    }
+                }
+            }
+        }
+
+        let mut ranked = occurrences
+            .into_iter()
+            .map(|(kmer, (a, c))| {
+                let p = self.log_pvalue(a, primary.len(), c, control.len());
+                (p, kmer)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|x, y| x.0.total_cmp(&y.0).then(x.1.cmp(&y.1)));
+        ranked
+            .into_iter()
+            .take(self.seeds)
+            .map(|(_, kmer)| kmer.into_iter().map(|x| 1 << x).collect())
+            .collect()
+    }
+
+    /// Generalize a word by adding symbols while this improves enrichment.
+    fn generalize(
+        &self,
+        word: Word,
+        primary: &[EncodedSequence<Dna>],
+        control: &[EncodedSequence<Dna>],
+    ) -> Word {
+        let mut pvalue = self.word_pvalue(&word, primary, control);
+        let mut best = word;
+        loop {
+            let degenerate = best.iter().filter(|m| m.count_ones() > 1).count();
+            let mut improved = None;
+            for i in 0..best.len() {
+                if best[i].count_ones() == 1 && degenerate >= self.max_degenerate {
+                    continue;
+                }
+                for s in Dna::symbols().iter().map(|s| s.as_index()) {
+                    if s == Dna::default_symbol().as_index() || best[i] & (1 << s) != 0 {
+                        continue;
+                    }
+                    let mut candidate = best.clone();
+                    candidate[i] |= 1 << s;
+                    let p = self.word_pvalue(&candidate, primary, control);
+                    if p < pvalue {
+                        pvalue = p;
+                        improved = Some(candidate);
+                    }
+                }
+            }
+            match improved {
+                Some(word) => best = word,
+                None => return best,
+            }
+        }
+    }
+
+    /// Stripe the sequences long enough to contain a site.
+    fn stripe<C: StrictlyPositive>(
+        &self,
+        sequences: &[EncodedSequence<Dna>],
+    ) -> Vec<Option<Strands<Dna, C>>> {
+        let reverse = reverse_strand(self.both_strands);
+        sequences
+            .iter()
+            .map(|seq| {
+                (seq.len() >= self.width).then(|| Strands::new(seq, self.width - 1, reverse))
+            })
+            .collect()
+    }
+
+    /// Find the best site of every sequence.
+    fn best_hits<C, P>(
+        &self,
+        pipeline: &P,
+        pssm: &ScoringMatrix<Dna>,
+        striped: &[Option<Strands<Dna, C>>],
+    ) -> Vec<Option<Hit>>
+    where
+        C: StrictlyPositive,
+        P: Score<Dna, C> + BestPosition<C>,
+    {
+        striped
+            .iter()
+            .enumerate()
+            .map(|(sequence, strands)| {
+                let strands = strands.as_ref()?;
+                let scores = pipeline.score(&strands.direct, pssm);
+                let position = pipeline.best_position(&scores)?;
+                let mut best = Hit {
+                    sequence,
+                    position,
+                    strand: Strand::Direct,
+                    score: scores[position],
+                };
+                if let Some(reverse) = strands.reverse.as_ref() {
+                    let scores = pipeline.score(reverse, pssm);
+                    let position = pipeline.best_position(&scores)?;
+                    if scores[position] > best.score {
+                        best = Hit {
+                            sequence,
+                            position: scores.len() - position - 1,
+                            strand: Strand::Reverse,
+                            score: scores[position],
+                        };
+                    }
+                }
+                Some(best)
+            })
+            .collect()
+    }
+
+    /// Extract the sequence of a site, on the strand of the site.
+    fn site(&self, sequences: &[EncodedSequence<Dna>], hit: &Hit) -> EncodedSequence<Dna> {
+        let site = sequences[hit.sequence].slice(hit.position..hit.position + self.width);
+        match hit.strand {
+            Strand::Direct => site,
+            Strand::Reverse => site.reverse_complement(),
+        }
+    }
+
+    /// Find all the matches of a word in the given sequences.
+    ///
+    /// Windows matching on both strands, such as the matches of palindromic
+    /// words, are only reported once, on the direct strand.
+    fn word_sites(&self, word: &[u8], sequences: &[EncodedSequence<Dna>]) -> Vec<Hit> {
+        let rc = reverse_complement(word);
+        let mut sites = Vec::new();
+        for (sequence, seq) in sequences.iter().enumerate() {
+            for (position, window) in seq[..].windows(self.width).enumerate() {
+                let strand = if matches(word, window) {
+                    Strand::Direct
+                } else if self.both_strands && matches(&rc, window) {
+                    Strand::Reverse
+                } else {
+                    continue;
+                };
+                sites.push(Hit {
+                    sequence,
+                    position,
+                    strand,
+                    score: 0.0,
+                });
+            }
+        }
+        sites
+    }
+
+    /// Refine a word into a motif by iteratively rescanning the sequences.
+    fn refine<C, P>(
+        &self,
+        pipeline: &P,
+        word: &[u8],
+        primary: &[EncodedSequence<Dna>],
+        control: &[EncodedSequence<Dna>],
+        striped_primary: &[Option<Strands<Dna, C>>],
+        striped_control: &[Option<Strands<Dna, C>>],
+    ) -> Option<DiscriminativeMotif>
+    where
+        C: StrictlyPositive,
+        P: Score<Dna, C> + BestPosition<C>,
+    {
+        // use all the matches of the word as initial sites
+        let mut sites = self.word_sites(word, primary);
+        if sites.is_empty() {
+            return None;
+        }
+
+        let mut motif = None;
+        for _ in 0..self.refinements.max(1) {
+            let counts =
+                CountMatrix::from_sequences(sites.iter().map(|hit| self.site(primary, hit)))
+                    .unwrap();
+            let prior = BackgroundPrior::new(self.pseudocount, self.background.clone());
+            let pssm = counts.to_freq(prior).to_scoring(self.background.clone());
+
+            // select the threshold maximizing the enrichment
+            let hits = self.best_hits(pipeline, &pssm, striped_primary);
+            let mut positives = hits
+                .iter()
+                .flatten()
+                .map(|hit| hit.score)
+                .filter(|x| x.is_finite())
+                .collect::<Vec<f32>>();
+            let mut negatives = self
+                .best_hits(pipeline, &pssm, striped_control)
+                .into_iter()
+                .flatten()
+                .map(|hit| hit.score)
+                .collect::<Vec<f32>>();
+            positives.sort_by(|x, y| y.total_cmp(x));
+            negatives.sort_by(|x, y| y.total_cmp(x));
+            let mut best: Option<(f64, f32, usize, usize)> = None;
+            let mut c = 0;
+            for (i, &t) in positives.iter().enumerate() {
+                if positives.get(i + 1) == Some(&t) {
+                    continue;
+                }
+                while c < negatives.len() && negatives[c] >= t {
+                    c += 1;
+                }
+                let p = self.log_pvalue(i + 1, primary.len(), c, control.len());
+                if best.map(|b| p < b.0).unwrap_or(true) {
+                    best = Some((p, t, i + 1, c));
+                }
+            }
+            let (log_pvalue, threshold, a, c) = best?;
+
+            let new_sites = hits
+                .into_iter()
+                .flatten()
+                .filter(|hit| hit.score >= threshold)
+                .collect::<Vec<Hit>>();
+            let converged = new_sites.len() == sites.len()
+                && new_sites.iter().zip(sites.iter()).all(|(x, y)| {
+                    x.sequence == y.sequence && x.position == y.position && x.strand == y.strand
+                });
+            sites = new_sites;
+            motif = Some(DiscriminativeMotif {
+                word: iupac(word),
+                counts: CountMatrix::from_sequences(
+                    sites.iter().map(|hit| self.site(primary, hit)),
+                )
+                .unwrap(),
+                threshold,
+                primary: a,
+                control: c,
+                log_pvalue,
+                sites: sites.clone(),
+            });
+            if converged {
+                break;
+            }
+        }
+        motif
+    }
+
+    /// Replace all the sites scoring above the threshold with wildcards.
+    fn mask<C, P>(
+        &self,
+        pipeline: &P,
+        pssm: &ScoringMatrix<Dna>,
+        threshold: f32,
+        sequences: &mut [EncodedSequence<Dna>],
+        striped: &[Option<Strands<Dna, C>>],
+    ) where
+        C: StrictlyPositive,
+        P: Score<Dna, C> + Threshold<C>,
+    {
+        for (seq, strands) in sequences.iter_mut().zip(striped) {
+            let strands = match strands {
+                Some(strands) => strands,
+                None => continue,
+            };
+            let mut data = seq[..].to_vec();
+            let scores = pipeline.score(&strands.direct, pssm);
+            for position in pipeline.threshold(&scores, threshold) {
+                data[position..position + self.width].fill(Nucleotide::N);
+            }
+            if let Some(reverse) = strands.reverse.as_ref() {
+                let scores = pipeline.score(reverse, pssm);
+                for position in pipeline.threshold(&scores, threshold) {
+                    let start = scores.len() - position - 1;
+                    data[start..start + self.width].fill(Nucleotide::N);
+                }
+            }
+            *seq = EncodedSequence::new(data);
+        }
+    }
+
+    /// Discover motifs enriched in the primary sequences.
+    ///
+    /// Motifs are returned in the order they were found, which is also the
+    /// order of their enrichment before masking. The sites of each motif
+    /// are found in the primary sequences after masking the sites of the
+    /// previous motifs.
+    pub fn run<C, P>(
+        &self,
+        pipeline: &P,
+        primary: &[EncodedSequence<Dna>],
+        control: &[EncodedSequence<Dna>],
+    ) -> Vec<DiscriminativeMotif>
+    where
+        C: StrictlyPositive,
+        P: Score<Dna, C> + BestPosition<C> + Threshold<C>,
+    {
+        let mut primary = primary.to_vec();
+        let mut control = control.to_vec();
+        let mut motifs = Vec::new();
+        while motifs.len() < self.max_motifs {
+            let striped_primary = self.stripe::<C>(&primary);
+            let striped_control = self.stripe::<C>(&control);
+            let best = self
+                .seed_words(&primary, &control)
+                .into_iter()
+                .map(|word| self.generalize(word, &primary, &control))
+                .filter_map(|word| {
+                    self.refine(
+                        pipeline,
+                        &word,
+                        &primary,
+                        &control,
+                        &striped_primary,
+                        &striped_control,
+                    )
+                })
+                .min_by(|x, y| x.log_pvalue.total_cmp(&y.log_pvalue));
+            let motif = match best {
+                Some(motif) if motif.log_pvalue <= self.pvalue.ln() => motif,
+                _ => break,
+            };
+
+            let prior = BackgroundPrior::new(self.pseudocount, self.background.clone());
+            let pssm = motif
+                .counts
+                .to_freq(prior)
+                .to_scoring(self.background.clone());
+            self.mask(
+                pipeline,
+                &pssm,
+                motif.threshold,
+                &mut primary,
+                &striped_primary,
+            );
+            self.mask(
+                pipeline,
+                &pssm,
+                motif.threshold,
+                &mut control,
+                &striped_control,
+            );
+            motifs.push(motif);
+        }
+        motifs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use typenum::consts::U32;
+
+    use super::*;
+
+    use crate::pli::Pipeline;
+
+    /// Generate pseudo-random sequences with a linear congruential generator.
+    fn background_sequences(n: usize, length: usize, seed: u64) -> Vec<String> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                (0..length)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        b"ACGT"[(state >> 33) as usize % 4] as char
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn planted(n: usize, sites: &[usize]) -> Vec<EncodedSequence<Dna>> {
+        background_sequences(n, 60, 12345)
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut s)| {
+                if let Some(&p) = sites.get(i) {
+                    s.replace_range(p..p + 8, "TTGACGCA");
+                }
+                EncodedSequence::encode(&s).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seed_kmers() {
+        let positions = [3, 10, 25, 40, 7, 18, 33, 50, 1, 12];
+        let sequences = planted(10, &positions);
+        let em = Em::<Dna>::new(8).with_seeds(1);
+        let seeds = em.seed_kmers(&sequences);
+        let seed = seeds[0]
+            .iter()
+            .map(|&i| Dna::symbols()[i].as_char())
+            .collect::<String>();
+        assert_eq!(seed, "TTGACGCA");
+    }
+
+    #[test]
+    fn test_oops() {
+        let positions = [3, 10, 25, 40, 7, 18, 33, 50, 1, 12];
+        let sequences = planted(10, &positions);
+        let pli = Pipeline::<Dna, _>::generic();
+        let motif = Em::<Dna>::new(8).run::<U32, _>(&pli, &sequences).unwrap();
+        assert_eq!(motif.sites.len(), 10);
+        for (site, &p) in motif.sites.iter().zip(positions.iter()) {
+            assert_eq!(site.position, p);
+        }
+        assert_eq!(motif.counts.to_freq(0.0).consensus(), "TTGACGCA");
+        assert!(motif.log_likelihood > 0.0);
+    }
+
+    #[test]
+    fn test_zoops() {
+        let positions = [3, 10, 25, 40, 7, 18, 33, 50];
+        let sequences = planted(12, &positions);
+        let pli = Pipeline::<Dna, _>::generic();
+        let motif = Em::<Dna>::new(8)
+            .with_model(Model::Zoops)
+            .run::<U32, _>(&pli, &sequences)
+            .unwrap();
+        for (i, &p) in positions.iter().enumerate() {
+            let site = motif.sites.iter().find(|s| s.sequence == i).unwrap();
+            assert_eq!(site.position, p);
+        }
+        let weakest = motif.sites[..8]
+            .iter()
+            .map(|s| s.score)
+            .fold(f32::MAX, f32::min);
+        assert!(motif.sites[8..].iter().all(|s| s.score < weakest));
+        assert_eq!(motif.counts.to_freq(0.0).consensus(), "TTGACGCA");
+    }
+
+    #[test]
+    fn test_anr() {
+        let first = [3, 10, 25, 40, 7, 18];
+        let second = [30, 45, 50, 2, 36, 44];
+        let mut sequences = planted(6, &first);
+        sequences.extend(planted(6, &second));
+        let pli = Pipeline::<Dna, _>::generic();
+        let motif = Em::<Dna>::new(8)
+            .with_model(Model::Anr)
+            .run::<U32, _>(&pli, &sequences)
+            .unwrap();
+        for (i, &p) in first.iter().chain(second.iter()).enumerate() {
+            let site = motif
+                .sites
+                .iter()
+                .find(|s| s.sequence == i && s.position == p);
+            assert!(site.is_some(), "missing site at {}:{}", i, p);
+        }
+        assert_eq!(motif.counts.to_freq(0.0).consensus(), "TTGACGCA");
+    }
+
+    #[test]
+    fn test_iupac() {
+        let word = vec![0b0001, 0b0101, 0b1111, 0b1000];
+        assert_eq!(iupac(&word), "AWNG");
+        assert_eq!(iupac(&reverse_complement(&word)), "CNWT");
+    }
+
+    #[test]
+    fn test_discriminative() {
+        let positions = [3, 10, 25, 40, 7, 18, 33, 50, 1, 12, 5, 22, 37, 44, 9, 16];
+        let mut primary = planted(16, &positions);
+        // plant the reverse complement in some sequences
+        for seq in primary[12..].iter_mut() {
+            let rc = seq.reverse_complement();
+            *seq = rc;
+        }
+        let control = background_sequences(16, 60, 67890)
+            .into_iter()
+            .map(|s| EncodedSequence::encode(&s).unwrap())
+            .collect::<Vec<_>>();
+
+        for statistic in [Statistic::Fisher, Statistic::Binomial] {
+            let pli = Pipeline::<Dna, _>::generic();
+            let motifs = Discriminative::new(8)
+                .with_statistic(statistic)
+                .with_max_motifs(1)
+                .run::<U32, _>(&pli, &primary, &control);
+            assert_eq!(motifs.len(), 1);
+            let motif = &motifs[0];
+            assert_eq!(motif.counts.to_freq(0.0).consensus(), "TTGACGCA");
+            assert!(motif.primary >= 16);
+            assert!(motif.control <= 2);
+            assert!(motif.log_pvalue < 0.05f64.ln());
+            for (i, &p) in positions.iter().enumerate() {
+                let hit = motif.sites.iter().find(|h| h.sequence == i).unwrap();
+                if i < 12 {
+                    assert_eq!(hit.strand, Strand::Direct);
+                    assert_eq!(hit.position, p);
+                } else {
+                    assert_eq!(hit.strand, Strand::Reverse);
+                    assert_eq!(hit.position, 60 - p - 8);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_word_sites_palindrome() {
+        let word = b"ACGCGT"
+            .iter()
+            .map(|&c| 1 << Nucleotide::from_ascii(c).unwrap().as_index())
+            .collect::<Word>();
+        let sequences = [EncodedSequence::encode("TTACGCGTTTACGCGTAA").unwrap()];
+        let sites = Discriminative::new(6).word_sites(&word, &sequences);
+        assert_eq!(sites.len(), 2);
+        assert!(sites.iter().all(|hit| hit.strand == Strand::Direct));
+        assert_eq!(sites[0].position, 2);
+        assert_eq!(sites[1].position, 10);
+    }
+}
@@ -0,0 +1,717 @@
+//! Motif enrichment analysis between two sets of sequences.
+//!
+//! Following AME (McLeay & Bailey, 2010), every motif of a database is
+//! scanned against a set of primary sequences, such as ChIP-seq peaks,
+//! and a set of control sequences, such as shuffled or background regions.
+//! Each sequence is summarized by a single [`Feature`], and the features
+//! of both sets are compared with a one-sided test of the chosen
+//! [`Method`]. P-values are finally adjusted for the number of motifs
+//! tested with the Benjamini-Hochberg procedure.
+//!
+//! The [`Centrality`] analysis follows CentriMo (Bailey & Machanick, 2012)
+//! instead, and tests whether the sites of a motif are concentrated in the
+//! center of a set of equal-length sequences.
+
+use std::ops::Range;
+
+use super::abc::Alphabet;
+use super::abc::ComplementableAlphabet;
+use super::err::InvalidData;
+use super::num::StrictlyPositive;
+use super::pli::BestPosition;
+use super::pli::Score;
+use super::pli::Threshold;
+use super::pwm::ScoringMatrix;
+use super::seq::reverse_strand;
+use super::seq::EncodedSequence;
+use super::seq::ReverseStrand;
+use super::seq::Strands;
+use super::stats::benjamini_hochberg;
+use super::stats::binomial_test;
+use super::stats::fisher_exact;
+use super::stats::ln_normal_sf;
+
+// --- Feature -----------------------------------------------------------------
+
+/// The value used to summarize the occurrences of a motif in a sequence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feature {
+    /// The best score of the motif in the sequence.
+    MaxScore,
+    /// The number of positions scoring at least the given threshold.
+    Hits(f32),
+}
+
+// --- Method ------------------------------------------------------------------
+
+/// The test used to compare the features of primary and control sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Fisher's exact test on the number of sequences with a site.
+    ///
+    /// With [`Feature::Hits`], a sequence has a site if it has at least one
+    /// hit. With [`Feature::MaxScore`], the score threshold minimizing the
+    /// p-value is selected, and the p-value is corrected for the number of
+    /// thresholds tried, as done in AME.
+    Fisher,
+    /// Wilcoxon rank-sum test of the features, using the normal
+    /// approximation with a correction for ties.
+    RankSum,
+    /// Score test for the slope of a logistic regression predicting whether
+    /// a sequence is a primary sequence from its feature.
+    Logistic,
+}
+
+// --- MotifEnrichment ---------------------------------------------------------
+
+/// The enrichment of a single motif in the primary sequences.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotifEnrichment {
+    /// The index of the motif in the database.
+    pub index: usize,
+    /// The effect size of the test: the log odds ratio for the Fisher test,
+    /// or the z-score for the rank-sum and logistic tests.
+    pub statistic: f64,
+    /// The natural logarithm of the p-value.
+    pub log_pvalue: f64,
+    /// The natural logarithm of the Benjamini-Hochberg adjusted p-value.
+    pub log_adjusted: f64,
+}
+
+// --- Enrichment --------------------------------------------------------------
+
+/// An AME-like motif enrichment analysis.
+#[derive(Clone, Debug)]
+pub struct Enrichment<A: Alphabet> {
+    feature: Feature,
+    method: Method,
+    reverse: Option<ReverseStrand<A>>,
+}
+
+impl<A: Alphabet> Enrichment<A> {
+    /// Create a new enrichment analysis with the given feature and test.
+    pub fn new(feature: Feature, method: Method) -> Self {
+        Self {
+            feature,
+            method,
+            reverse: None,
+        }
+    }
+
+    /// Compute the feature of every sequence with a finite value.
+    ///
+    /// Sequences shorter than the motif are skipped.
+    fn features<C, P>(
+        &self,
+        pipeline: &P,
+        pssm: &ScoringMatrix<A>,
+        striped: &[Strands<A, C>],
+    ) -> Vec<f64>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + BestPosition<C> + Threshold<C>,
+    {
+        striped
+            .iter()
+            .filter(|strands| strands.length >= pssm.len())
+            .filter_map(|strands| {
+                let all = std::iter::once(&strands.direct).chain(strands.reverse.as_ref());
+                let mut value = match self.feature {
+                    Feature::MaxScore => f32::NEG_INFINITY,
+                    Feature::Hits(_) => 0.0,
+                };
+                for seq in all {
+                    let scores = pipeline.score(seq, pssm);
+                    match self.feature {
+                        Feature::MaxScore => {
+                            if let Some(i) = pipeline.best_position(&scores) {
+                                value = value.max(scores[i]);
+                            }
+                        }
+                        Feature::Hits(t) => {
+                            value += pipeline.threshold(&scores, t).len() as f32;
+                        }
+                    }
+                }
+                value.is_finite().then_some(value as f64)
+            })
+            .collect()
+    }
+
+    /// Test the enrichment of features, returning the statistic and p-value.
+    fn test(&self, primary: &[f64], control: &[f64]) -> (f64, f64) {
+        match self.method {
+            Method::Fisher => self.fisher(primary, control),
+            Method::RankSum => rank_sum(primary, control),
+            Method::Logistic => logistic(primary, control),
+        }
+    }
+
+    /// Test the enrichment with Fisher's exact test.
+    fn fisher(&self, primary: &[f64], control: &[f64]) -> (f64, f64) {
+        let (n, m) = (primary.len(), control.len());
+        let odds = |a: usize, c: usize| {
+            let (a, b, c, d) = (a as f64, (n - a) as f64, c as f64, (m - c) as f64);
+            ((a + 0.5) * (d + 0.5) / ((b + 0.5) * (c + 0.5))).ln()
+        };
+        if let Feature::Hits(_) = self.feature {
+            let a = primary.iter().filter(|&&x| x > 0.0).count();
+            let c = control.iter().filter(|&&x| x > 0.0).count();
+            return (odds(a, c), fisher_exact(a, n - a, c, m - c));
+        }
+
+        let mut positives = primary.to_vec();
+        let mut negatives = control.to_vec();
+        positives.sort_by(|x, y| y.total_cmp(x));
+        negatives.sort_by(|x, y| y.total_cmp(x));
+        let mut best = (0.0, 0.0);
+        let mut thresholds = 0;
+        let mut c = 0;
+        for (i, &t) in positives.iter().enumerate() {
+            if positives.get(i + 1) == Some(&t) {
+                continue;
+            }
+            thresholds += 1;
+            while c < m && negatives[c] >= t {
+                c += 1;
+            }
+            let p = fisher_exact(i + 1, n - i - 1, c, m - c);
+            if p < best.1 {
+                best = (odds(i + 1, c), p);
+            }
+        }
+        if thresholds > 0 {
+            best.1 = (best.1 + (thresholds as f64).ln()).min(0.0);
+        }
+        best
+    }
+
+    /// Test the enrichment of every motif in the primary sequences.
+    ///
+    /// Results are sorted by increasing p-value. Motifs for which one of
+    /// the sets has no sequence long enough to be scored get a p-value of 1.
+    pub fn run<C, P>(
+        &self,
+        pipeline: &P,
+        motifs: &[ScoringMatrix<A>],
+        primary: &[EncodedSequence<A>],
+        control: &[EncodedSequence<A>],
+    ) -> Vec<MotifEnrichment>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + BestPosition<C> + Threshold<C>,
+    {
+        let wrap = motifs.iter().map(|m| m.len()).max().unwrap_or(1) - 1;
+        let striped_primary = primary
+            .iter()
+            .map(|seq| Strands::new(seq, wrap, self.reverse))
+            .collect::<Vec<Strands<A, C>>>();
+        let striped_control = control
+            .iter()
+            .map(|seq| Strands::new(seq, wrap, self.reverse))
+            .collect::<Vec<Strands<A, C>>>();
+
+        let tests = motifs
+            .iter()
+            .map(|pssm| {
+                let x = self.features(pipeline, pssm, &striped_primary);
+                let y = self.features(pipeline, pssm, &striped_control);
+                if x.is_empty() || y.is_empty() {
+                    (0.0, 0.0)
+                } else {
+                    self.test(&x, &y)
+                }
+            })
+            .collect::<Vec<_>>();
+        let log_pvalues = tests.iter().map(|t| t.1).collect::<Vec<_>>();
+        let log_adjusted = benjamini_hochberg(&log_pvalues);
+
+        let mut results = tests
+            .into_iter()
+            .zip(log_adjusted)
+            .enumerate()
+            .map(
+                |(index, ((statistic, log_pvalue), log_adjusted))| MotifEnrichment {
+                    index,
+                    statistic,
+                    log_pvalue,
+                    log_adjusted,
+                },
+            )
+            .collect::<Vec<_>>();
+        results.sort_by(|x, y| {
+            x.log_pvalue
+                .total_cmp(&y.log_pvalue)
+                .then(x.index.cmp(&y.index))
+        });
+        results
+    }
+}
+
+impl<A: ComplementableAlphabet> Enrichment<A> {
+    /// Scan both strands of the sequences, or only the direct strand.
+    pub fn with_both_strands(mut self, both_strands: bool) -> Self {
+        self.reverse = reverse_strand(both_strands);
+        self
+    }
+}
+
+/// Compute the ranks of the given values, averaging the ranks of ties.
+///
+/// Returns the ranks and the tie correction term `sum(t^3 - t)`.
+fn ranks(values: &[f64]) -> (Vec<f64>, f64) {
+    let mut order = (0..values.len()).collect::<Vec<usize>>();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i + 1;
+        while j < order.len() && values[order[j]] == values[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.0;
+        for &k in &order[i..j] {
+            ranks[k] = rank;
+        }
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+    (ranks, ties)
+}
+
+/// Test the enrichment with the one-sided Wilcoxon rank-sum test.
+fn rank_sum(primary: &[f64], control: &[f64]) -> (f64, f64) {
+    let (n1, n2) = (primary.len() as f64, control.len() as f64);
+    let values = primary.iter().chain(control).cloned().collect::<Vec<_>>();
+    let (ranks, ties) = ranks(&values);
+    let r1: f64 = ranks[..primary.len()].iter().sum();
+    let u = r1 - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let z = (u - n1 * n2 / 2.0 - 0.5) / variance.sqrt();
+    (z, ln_normal_sf(z))
+}
+
+/// Test the enrichment with the logistic regression score test.
+///
+/// Under the null hypothesis of a zero slope, the score statistic for the
+/// slope is `sum((y - mean(y)) * x)`, with variance
+/// `mean(y) * (1 - mean(y)) * sum((x - mean(x))^2)`.
+fn logistic(primary: &[f64], control: &[f64]) -> (f64, f64) {
+    let n = (primary.len() + control.len()) as f64;
+    let p = primary.len() as f64 / n;
+    let mean = primary.iter().chain(control).sum::<f64>() / n;
+    let score = (1.0 - p) * primary.iter().map(|x| x - mean).sum::<f64>()
+        - p * control.iter().map(|x| x - mean).sum::<f64>();
+    let ss: f64 = primary
+        .iter()
+        .chain(control)
+        .map(|x| (x - mean) * (x - mean))
+        .sum();
+    let variance = p * (1.0 - p) * ss;
+    if variance <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let z = score / variance.sqrt();
+    (z, ln_normal_sf(z))
+}
+
+// --- Centrality --------------------------------------------------------------
+
+/// The sites collected in each sequence for central enrichment analysis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sites {
+    /// The best site of each sequence.
+    Best,
+    /// All the sites scoring at least the given threshold.
+    Hits(f32),
+}
+
+/// The central enrichment of a single motif.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CentralEnrichment {
+    /// The index of the motif in the database.
+    pub index: usize,
+    /// The number of sites starting at each position of the sequences.
+    pub counts: Vec<usize>,
+    /// The most enriched central window of site positions.
+    pub window: Range<usize>,
+    /// The number of sites in the most enriched window.
+    pub sites: usize,
+    /// The total number of sites.
+    pub total: usize,
+    /// The natural logarithm of the p-value, corrected for the number of
+    /// windows tested.
+    pub log_pvalue: f64,
+    /// The natural logarithm of the Benjamini-Hochberg adjusted p-value.
+    pub log_adjusted: f64,
+}
+
+/// A CentriMo-like central enrichment analysis.
+///
+/// Sites are collected from sequences of equal length, typically centered
+/// on ChIP-seq peak summits, and their start positions are counted. Under
+/// the null hypothesis, sites are distributed uniformly along the sequences,
+/// so the number of sites falling in a central window of `k` out of `m`
+/// possible positions follows a binomial distribution with a success
+/// probability of `k / m`. Every central window is tested, and the most
+/// enriched one is reported.
+#[derive(Clone, Debug)]
+pub struct Centrality<A: Alphabet> {
+    sites: Sites,
+    max_width: Option<usize>,
+    reverse: Option<ReverseStrand<A>>,
+}
+
+impl<A: Alphabet> Centrality<A> {
+    /// Create a new central enrichment analysis collecting the given sites.
+    pub fn new(sites: Sites) -> Self {
+        Self {
+            sites,
+            max_width: None,
+            reverse: None,
+        }
+    }
+
+    /// Only test central windows spanning at most the given number of positions.
+    pub fn with_max_width<W: Into<Option<usize>>>(mut self, max_width: W) -> Self {
+        self.max_width = max_width.into();
+        self
+    }
+
+    /// Count the sites starting at each position of the sequences.
+    ///
+    /// Sites on the reverse strand are counted at the position where they
+    /// start on the direct strand.
+    fn counts<C, P>(
+        &self,
+        pipeline: &P,
+        pssm: &ScoringMatrix<A>,
+        striped: &[Strands<A, C>],
+    ) -> Vec<usize>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + BestPosition<C> + Threshold<C>,
+    {
+        let length = striped[0].length;
+        let mut counts = vec![0; length - pssm.len() + 1];
+        for strands in striped {
+            let direct = pipeline.score(&strands.direct, pssm);
+            let reverse = strands
+                .reverse
+                .as_ref()
+                .map(|seq| pipeline.score(seq, pssm));
+            let last = counts.len() - 1;
+            match self.sites {
+                Sites::Best => {
+                    let mut best: Option<(f32, usize)> =
+                        pipeline.best_position(&direct).map(|i| (direct[i], i));
+                    if let Some(scores) = reverse.as_ref() {
+                        if let Some(i) = pipeline.best_position(scores) {
+                            if best.map(|b| scores[i] > b.0).unwrap_or(true) {
+                                best = Some((scores[i], last - i));
+                            }
+                        }
+                    }
+                    if let Some((score, i)) = best {
+                        if score.is_finite() {
+                            counts[i] += 1;
+                        }
+                    }
+                }
+                Sites::Hits(t) => {
+                    for i in pipeline.threshold(&direct, t) {
+                        counts[i] += 1;
+                    }
+                    if let Some(scores) = reverse.as_ref() {
+                        for i in pipeline.threshold(scores, t) {
+                            counts[last - i] += 1;
+                        }
+                    }
+                }
+            }
+        }
+        counts
+    }
+
+    /// Find the most enriched central window of a site position histogram.
+    fn best_window(&self, counts: &[usize]) -> (Range<usize>, usize, f64) {
+        let m = counts.len();
+        let total = counts.iter().sum::<usize>();
+        let max_width = self.max_width.unwrap_or(m).clamp(1, m);
+        let mut best = (0..m, total, 0.0);
+        let mut windows = 0;
+        // windows have the same parity as the number of positions so that
+        // they are exactly centered
+        let mut k = 2 - m % 2;
+        while k <= max_width {
+            let window = (m - k) / 2..(m + k) / 2;
+            let x = counts[window.clone()].iter().sum::<usize>();
+            let p = binomial_test(x, total, k as f64 / m as f64);
+            if p < best.2 {
+                best = (window, x, p);
+            }
+            windows += 1;
+            k += 2;
+        }
+        best.2 = (best.2 + (windows.max(1) as f64).ln()).min(0.0);
+        best
+    }
+
+    /// Test the central enrichment of every motif in the given sequences.
+    ///
+    /// Results are sorted by increasing p-value. Motifs longer than the
+    /// sequences get an empty histogram and a p-value of 1.
+    ///
+    /// # Errors
+    /// Returns an error if the sequences do not all have the same length.
+    pub fn run<C, P>(
+        &self,
+        pipeline: &P,
+        motifs: &[ScoringMatrix<A>],
+        sequences: &[EncodedSequence<A>],
+    ) -> Result<Vec<CentralEnrichment>, InvalidData>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C> + BestPosition<C> + Threshold<C>,
+    {
+        let length = sequences.first().map(|s| s.len()).unwrap_or(0);
+        if sequences.iter().any(|s| s.len() != length) {
+            return Err(InvalidData);
+        }
+        let wrap = motifs.iter().map(|m| m.len()).max().unwrap_or(1) - 1;
+        let striped = sequences
+            .iter()
+            .map(|seq| Strands::new(seq, wrap, self.reverse))
+            .collect::<Vec<Strands<A, C>>>();
+
+        let mut results = motifs
+            .iter()
+            .enumerate()
+            .map(|(index, pssm)| {
+                if striped.is_empty() || pssm.len() > length {
+                    return CentralEnrichment {
+                        index,
+                        counts: Vec::new(),
+                        window: 0..0,
+                        sites: 0,
+                        total: 0,
+                        log_pvalue: 0.0,
+                        log_adjusted: 0.0,
+                    };
+                }
+                let counts = self.counts(pipeline, pssm, &striped);
+                let (window, sites, log_pvalue) = self.best_window(&counts);
+                CentralEnrichment {
+                    index,
+                    total: counts.iter().sum(),
+                    counts,
+                    window,
+                    sites,
+                    log_pvalue,
+                    log_adjusted: 0.0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let log_pvalues = results.iter().map(|r| r.log_pvalue).collect::<Vec<_>>();
+        for (result, q) in results.iter_mut().zip(benjamini_hochberg(&log_pvalues)) {
+            result.log_adjusted = q;
+        }
+        results.sort_by(|x, y| {
+            x.log_pvalue
+                .total_cmp(&y.log_pvalue)
+                .then(x.index.cmp(&y.index))
+        });
+        Ok(results)
+    }
+}
+
+impl<A: ComplementableAlphabet> Centrality<A> {
+    /// Collect sites on both strands, or only on the direct strand.
+    pub fn with_both_strands(mut self, both_strands: bool) -> Self {
+        self.reverse = reverse_strand(both_strands);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use typenum::consts::U32;
+
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::pli::Pipeline;
+    use crate::pwm::CountMatrix;
+
+    fn encode(sequences: &[&str]) -> Vec<EncodedSequence<Dna>> {
+        sequences
+            .iter()
+            .map(|s| EncodedSequence::encode(s).unwrap())
+            .collect()
+    }
+
+    fn motif(sites: &[&str]) -> ScoringMatrix<Dna> {
+        CountMatrix::from_sequences(encode(sites))
+            .unwrap()
+            .to_freq(0.25)
+            .to_scoring(None)
+    }
+
+    fn sequences() -> (Vec<EncodedSequence<Dna>>, Vec<EncodedSequence<Dna>>) {
+        let primary = encode(&[
+            "ATTGACGCATAGCTAGCTAG",
+            "CGATCGATTGACGCACGATA",
+            "TTTTGACGCAGGATCCAGTA",
+            "GATCGATCGGATTGACGCAT",
+            "TGCGTCAAGCTAGTCAGTCA",
+            "ACGTTGACGCAACGTACGTA",
+            "CCATTGACGCAGGCATGACT",
+            "TTGACGCATTTTCAGGGCCC",
+        ]);
+        let control = encode(&[
+            "ATGCATGCATGCATGCATGC",
+            "CGATCGATCGATCGATCGAT",
+            "GGGGCCCCAAAATTTTGGGG",
+            "ACACACACGTGTGTGTACAC",
+            "TATATATAGCGCGCGCTATA",
+            "CAGTCAGTCAGTCAGTCAGT",
+            "GATTACAGATTACAGATTAC",
+            "TCGATGCACGTAGCTAGCAT",
+        ]);
+        (primary, control)
+    }
+
+    #[test]
+    fn test_ranks() {
+        let (ranks, ties) = ranks(&[3.0, 1.0, 3.0, 2.0]);
+        assert_eq!(ranks, vec![3.5, 1.0, 3.5, 2.0]);
+        assert_eq!(ties, 6.0);
+    }
+
+    #[test]
+    fn test_rank_sum() {
+        let pli = Pipeline::<Dna, _>::generic();
+        let (primary, control) = sequences();
+        let motifs = [motif(&["TTGACGCA"]), motif(&["GGGGGGGG"])];
+        let results = Enrichment::new(Feature::MaxScore, Method::RankSum)
+            .with_both_strands(true)
+            .run::<U32, _>(&pli, &motifs, &primary, &control);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].index, 0);
+        assert!(results[0].statistic > 3.0);
+        assert!(results[0].log_pvalue < 0.001f64.ln());
+        assert!(results[0].log_adjusted >= results[0].log_pvalue);
+        assert!(results[1].log_pvalue > 0.05f64.ln());
+    }
+
+    #[test]
+    fn test_fisher() {
+        let pli = Pipeline::<Dna, _>::generic();
+        let (primary, control) = sequences();
+        let motifs = [motif(&["TTGACGCA"]), motif(&["GATTACA"])];
+        let results = Enrichment::new(Feature::Hits(10.0), Method::Fisher)
+            .with_both_strands(true)
+            .run::<U32, _>(&pli, &motifs, &primary, &control);
+        assert_eq!(results[0].index, 0);
+        assert!((results[0].log_pvalue - fisher_exact(8, 0, 0, 8)).abs() < 1e-9);
+        assert_eq!(results[1].index, 1);
+        assert_eq!(results[1].log_pvalue, 0.0);
+
+        // without the reverse strand, one primary sequence has no site
+        let results = Enrichment::new(Feature::Hits(10.0), Method::Fisher)
+            .run::<U32, _>(&pli, &motifs, &primary, &control);
+        assert!((results[0].log_pvalue - fisher_exact(7, 1, 0, 8)).abs() < 1e-9);
+
+        let results = Enrichment::new(Feature::MaxScore, Method::Fisher)
+            .with_both_strands(true)
+            .run::<U32, _>(&pli, &motifs, &primary, &control);
+        assert_eq!(results[0].index, 0);
+        assert!(results[0].log_pvalue < 0.01f64.ln());
+    }
+
+    #[test]
+    fn test_logistic() {
+        let pli = Pipeline::<Dna, _>::generic();
+        let (primary, control) = sequences();
+        let motifs = [motif(&["GATTACA"]), motif(&["TTGACGCA"])];
+        let results = Enrichment::new(Feature::MaxScore, Method::Logistic)
+            .with_both_strands(true)
+            .run::<U32, _>(&pli, &motifs, &primary, &control);
+        assert_eq!(results[0].index, 1);
+        assert!(results[0].statistic > 3.0);
+        assert!(results[0].log_pvalue < 0.001f64.ln());
+        assert!(results[1].statistic < 0.0);
+    }
+
+    #[test]
+    fn test_best_window() {
+        let centrality = Centrality::<Dna>::new(Sites::Best);
+        let counts = [1, 0, 1, 5, 6, 4, 0, 1, 1];
+        let (window, sites, log_pvalue) = centrality.best_window(&counts);
+        assert_eq!(window, 3..6);
+        assert_eq!(sites, 15);
+        let expected = binomial_test(15, 19, 3.0 / 9.0) + 5f64.ln();
+        assert!((log_pvalue - expected).abs() < 1e-9);
+
+        let centrality = centrality.with_max_width(1);
+        let (window, sites, _) = centrality.best_window(&counts);
+        assert_eq!(window, 4..5);
+        assert_eq!(sites, 6);
+    }
+
+    #[test]
+    fn test_centrality() {
+        let mut state = 42u64;
+        let mut random = |n: usize| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 33) as usize % n
+        };
+        let sequences = (0..30)
+            .map(|i| {
+                let mut s = (0..51)
+                    .map(|_| b"ACGT"[random(4)] as char)
+                    .collect::<String>();
+                let p = 20 + random(4);
+                s.replace_range(p..p + 8, if i % 2 == 0 { "TTGACGCA" } else { "TGCGTCAA" });
+                let q = random(44);
+                if q + 7 <= p || q >= p + 8 {
+                    s.replace_range(q..q + 7, "GATTACA");
+                }
+                EncodedSequence::encode(&s).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let pli = Pipeline::<Dna, _>::generic();
+        let motifs = [motif(&["GATTACA"]), motif(&["TTGACGCA"])];
+        let results = Centrality::new(Sites::Best)
+            .with_both_strands(true)
+            .run::<U32, _>(&pli, &motifs, &sequences)
+            .unwrap();
+        assert_eq!(results[0].index, 1);
+        assert_eq!(results[0].total, 30);
+        assert_eq!(results[0].counts.len(), 44);
+        assert!(results[0].window.start >= 18 && results[0].window.end <= 26);
+        assert!(results[0].sites >= 28);
+        assert!(results[0].log_adjusted < 1e-10f64.ln());
+        assert!(results[1].log_pvalue > 0.01f64.ln());
+
+        // without the reverse strand, only half of the sites are central
+        let results = Centrality::new(Sites::Hits(10.0))
+            .run::<U32, _>(&pli, &motifs[1..], &sequences)
+            .unwrap();
+        assert_eq!(results[0].total, 15);
+
+        let mut sequences = sequences;
+        sequences.push(EncodedSequence::encode("ACGT").unwrap());
+        assert!(Centrality::new(Sites::Best)
+            .run::<U32, _>(&pli, &motifs, &sequences)
+            .is_err());
+    }
+}
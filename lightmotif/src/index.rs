@@ -0,0 +1,326 @@
+//! Full-text index for scanning the same sequence with many motifs.
+//!
+//! A [`SuffixArray`] sorts all the suffixes of a sequence, so that the
+//! positions sharing a common prefix form a contiguous interval of the
+//! array. Searching a scoring matrix is then a depth-first traversal of the
+//! implicit suffix tree, extending prefixes column by column and pruning
+//! every prefix which cannot reach the threshold, whatever the symbols of
+//! the remaining columns. For high thresholds, only a tiny fraction of the
+//! sequence is ever visited.
+//!
+//! The index can be written to and read from disk, so that it only needs to
+//! be built once for a given sequence. Positions are stored as 32-bit
+//! integers, so sequences are limited to `u32::MAX` symbols.
+
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+
+use super::abc::Alphabet;
+use super::abc::Symbol;
+use super::pwm::ScoringMatrix;
+use super::seq::EncodedSequence;
+use super::util::bounds;
+use super::util::tolerance;
+
+/// The magic bytes at the start of a serialized suffix array.
+const MAGIC: &[u8; 4] = b"LMSA";
+
+// --- SuffixArray -------------------------------------------------------------
+
+/// A suffix array over an encoded sequence.
+#[derive(Clone, Debug)]
+pub struct SuffixArray<A: Alphabet> {
+    sequence: EncodedSequence<A>,
+    suffixes: Vec<u32>,
+}
+
+impl<A: Alphabet> SuffixArray<A> {
+    /// Build the suffix array of the given sequence.
+    ///
+    /// Suffixes are sorted by prefix doubling with a comparison sort, in
+    /// `O(n log² n)` time. The suffixes and two rank arrays are stored as
+    /// 32-bit integers, so construction needs about 12 bytes per symbol in
+    /// addition to the sequence.
+    ///
+    /// # Panics
+    /// Panics if the sequence is longer than `u32::MAX` symbols.
+    pub fn new(sequence: EncodedSequence<A>) -> Self {
+        let n = sequence.len();
+        assert!(
+            n <= u32::MAX as usize,
+            "sequence too long for a suffix array"
+        );
+        let mut suffixes = (0..n as u32).collect::<Vec<u32>>();
+        let mut ranks = sequence
+            .iter()
+            .into_iter()
+            .map(|x| x.as_index() as u32)
+            .collect::<Vec<u32>>();
+        let mut next = vec![0; n];
+        let mut k = 1;
+        if n > 1 {
+            loop {
+                // rank suffixes by their first `2k` symbols, using the ranks
+                // of their first `k` symbols
+                let key = |i: u32| {
+                    let i = i as usize;
+                    (ranks[i], ranks.get(i + k).map(|r| r + 1).unwrap_or(0))
+                };
+                suffixes.sort_unstable_by_key(|&i| key(i));
+                next[suffixes[0] as usize] = 0;
+                for w in 1..n {
+                    let different = key(suffixes[w - 1]) != key(suffixes[w]);
+                    next[suffixes[w] as usize] = next[suffixes[w - 1] as usize] + different as u32;
+                }
+                std::mem::swap(&mut ranks, &mut next);
+                if ranks[suffixes[n - 1] as usize] as usize == n - 1 {
+                    break;
+                }
+                k *= 2;
+            }
+        }
+        Self { sequence, suffixes }
+    }
+
+    /// The indexed sequence.
+    pub fn sequence(&self) -> &EncodedSequence<A> {
+        &self.sequence
+    }
+
+    /// The starting positions of the suffixes, in lexicographic order.
+    pub fn suffixes(&self) -> &[u32] {
+        &self.suffixes
+    }
+
+    /// The length of the indexed sequence.
+    pub fn len(&self) -> usize {
+        self.suffixes.len()
+    }
+
+    /// Check whether the indexed sequence is empty.
+    pub fn is_empty(&self) -> bool {
+        self.suffixes.is_empty()
+    }
+
+    /// Get the symbol of a suffix at the given depth, if any.
+    fn symbol(&self, suffix: usize, depth: usize) -> Option<usize> {
+        let i = self.suffixes[suffix] as usize + depth;
+        if i < self.sequence.len() {
+            Some(self.sequence[i].as_index())
+        } else {
+            None
+        }
+    }
+
+    /// Return the positions with a score equal to or greater than the threshold.
+    ///
+    /// Scores are accumulated column by column, as in the pipelines, so the
+    /// positions are the same as returned by
+    /// [`Threshold::threshold`](crate::pli::Threshold::threshold), and are
+    /// returned in increasing order.
+    pub fn search(&self, pssm: &ScoringMatrix<A>, threshold: f32) -> Vec<usize> {
+        let mut positions = Vec::new();
+        if pssm.len() > self.len() {
+            return positions;
+        }
+
+        // maximum score reachable after each column of the matrix
+        let weights = pssm.weights();
+        let bounds = bounds(pssm, &(0..pssm.len()).collect::<Vec<usize>>());
+        let cutoff = threshold - tolerance(pssm);
+
+        let mut stack = vec![(0, self.len(), 0, 0.0f32)];
+        while let Some((start, end, depth, partial)) = stack.pop() {
+            if depth == pssm.len() {
+                if partial >= threshold {
+                    positions.extend(self.suffixes[start..end].iter().map(|&i| i as usize));
+                }
+                continue;
+            }
+            // suffixes too short to extend come first in the interval
+            let mut lo = start
+                + self.suffixes[start..end]
+                    .partition_point(|&i| i as usize + depth >= self.sequence.len());
+            while lo < end {
+                let x = self.symbol(lo, depth).unwrap();
+                let hi = lo
+                    + self.suffixes[lo..end]
+                        .partition_point(|&i| self.sequence[i as usize + depth].as_index() <= x);
+                let score = partial + weights[depth][x];
+                if score + bounds[depth + 1] >= cutoff {
+                    stack.push((lo, hi, depth + 1, score));
+                }
+                lo = hi;
+            }
+        }
+
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Write the suffix array to the given writer.
+    ///
+    /// The sequence is written as text, followed by the positions of the
+    /// suffixes as little-endian 32-bit integers.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+        let text = self
+            .sequence
+            .iter()
+            .into_iter()
+            .map(|x| x.as_ascii())
+            .collect::<Vec<u8>>();
+        writer.write_all(&text)?;
+        for &i in self.suffixes.iter() {
+            writer.write_all(&i.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read a suffix array previously written with [`SuffixArray::write_to`].
+    ///
+    /// # Errors
+    /// Returns an error of kind [`ErrorKind::InvalidData`] if the data is
+    /// not a valid suffix array for the alphabet `A`, including when the
+    /// suffixes are not sorted, which is checked in linear time.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+        let mut buffer = [0; 8];
+
+        reader.read_exact(&mut buffer[..4])?;
+        if &buffer[..4] != MAGIC {
+            return Err(invalid("invalid magic bytes"));
+        }
+        reader.read_exact(&mut buffer)?;
+        let n = u32::try_from(u64::from_le_bytes(buffer))
+            .map_err(|_| invalid("sequence too long"))? as usize;
+
+        // avoid allocating the announced length before reading the data
+        let mut text = Vec::new();
+        reader.by_ref().take(n as u64).read_to_end(&mut text)?;
+        if text.len() != n {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        let symbols = text
+            .into_iter()
+            .map(A::Symbol::from_ascii)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid("invalid symbol"))?;
+
+        // read the suffixes and record their ranks, which also checks that
+        // every position appears exactly once
+        let mut suffixes = Vec::with_capacity(n);
+        let mut ranks = vec![u32::MAX; n];
+        let mut position = [0; 4];
+        for r in 0..n {
+            reader.read_exact(&mut position)?;
+            let i = u32::from_le_bytes(position);
+            if i as usize >= n || ranks[i as usize] != u32::MAX {
+                return Err(invalid("invalid suffix position"));
+            }
+            ranks[i as usize] = r as u32;
+            suffixes.push(i);
+        }
+
+        // adjacent suffixes are sorted if their first symbols are sorted,
+        // or if the suffixes following their first symbols are sorted
+        for w in suffixes.windows(2) {
+            let (i, j) = (w[0] as usize, w[1] as usize);
+            let (x, y) = (symbols[i].as_index(), symbols[j].as_index());
+            let sorted =
+                x < y || (x == y && (i + 1 == n || (j + 1 < n && ranks[i + 1] < ranks[j + 1])));
+            if !sorted {
+                return Err(invalid("unsorted suffixes"));
+            }
+        }
+
+        Ok(Self {
+            sequence: EncodedSequence::new(symbols),
+            suffixes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use typenum::consts::U32;
+
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::pli::Pipeline;
+    use crate::pli::Score;
+    use crate::pli::Threshold;
+    use crate::util::fixtures::pssm;
+    use crate::util::fixtures::sequence;
+
+    #[test]
+    fn test_new() {
+        let seq = EncodedSequence::<Dna>::encode("ACGTTAGGACGTAA").unwrap();
+        let sa = SuffixArray::new(seq.clone());
+        assert_eq!(sa.len(), seq.len());
+        let suffix = |i: usize| {
+            seq[i..]
+                .iter()
+                .map(|x| x.as_index())
+                .collect::<Vec<usize>>()
+        };
+        for w in sa.suffixes().windows(2) {
+            assert!(suffix(w[0] as usize) < suffix(w[1] as usize));
+        }
+        assert!(SuffixArray::new(EncodedSequence::<Dna>::new(Vec::new())).is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let pssm = pssm();
+        let seq = sequence(3000, 11)
+            .concat(&EncodedSequence::encode(&pssm.consensus()).unwrap())
+            .concat(&sequence(20, 3));
+        let sa = SuffixArray::new(seq.clone());
+
+        let pli = Pipeline::generic();
+        let mut striped = seq.to_striped::<U32>();
+        striped.configure(&pssm);
+        let scores = pli.score(&striped, &pssm);
+        let mut sorted = scores.iter().cloned().collect::<Vec<f32>>();
+        sorted.sort_by(|x, y| y.partial_cmp(x).unwrap());
+        for &threshold in [pssm.max_score(), 10.0, 0.0, sorted[0], sorted[5]].iter() {
+            let mut expected = pli.threshold(&scores, threshold);
+            expected.sort();
+            assert_eq!(sa.search(&pssm, threshold), expected);
+        }
+        assert_eq!(sa.search(&pssm, pssm.max_score()), vec![3000]);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let sa = SuffixArray::new(sequence(500, 5));
+        let mut buffer = Vec::new();
+        sa.write_to(&mut buffer).unwrap();
+        let read = SuffixArray::<Dna>::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(read.suffixes(), sa.suffixes());
+        assert_eq!(read.sequence().to_string(), sa.sequence().to_string());
+
+        // swapping two suffixes keeps a permutation, but not a sorted one
+        let mut swapped = buffer.clone();
+        let offset = 4 + 8 + sa.len() + 4 * 100;
+        let (x, y) = swapped[offset..offset + 8].split_at_mut(4);
+        x.swap_with_slice(y);
+        let err = SuffixArray::<Dna>::read_from(swapped.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let repeats = EncodedSequence::<Dna>::encode("AAAAAAACACACACNNNAAAAAAAAA").unwrap();
+        let mut written = Vec::new();
+        SuffixArray::new(repeats).write_to(&mut written).unwrap();
+        assert!(SuffixArray::<Dna>::read_from(written.as_slice()).is_ok());
+
+        buffer[0] = b'X';
+        let err = SuffixArray::<Dna>::read_from(buffer.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(SuffixArray::<Dna>::read_from(&buffer[..100]).is_err());
+    }
+}
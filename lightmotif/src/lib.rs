@@ -1,15 +1,32 @@
 #![doc = include_str!("../README.md")]
 
 extern crate generic_array;
+#[cfg(feature = "rand")]
+extern crate rand;
 extern crate typenum;
 
 pub mod abc;
+pub mod cluster;
+pub mod compare;
+pub mod crm;
 pub mod dense;
+pub mod discovery;
+pub mod enrichment;
 pub mod err;
+pub mod index;
+pub mod logo;
 pub mod num;
 pub mod pli;
+pub mod prior;
 pub mod pwm;
+#[cfg(feature = "rand")]
+pub mod sample;
+pub mod scan;
 pub mod seq;
+pub mod stats;
+mod util;
+pub mod variant;
+pub mod weighting;
 
 pub use abc::Alphabet;
 pub use abc::Dna;
@@ -23,5 +40,7 @@ pub use pwm::CountMatrix;
 pub use pwm::FrequencyMatrix;
 pub use pwm::ScoringMatrix;
 pub use pwm::WeightMatrix;
+pub use seq::AlignedSequence;
 pub use seq::EncodedSequence;
+pub use seq::PackedSequence;
 pub use seq::StripedSequence;
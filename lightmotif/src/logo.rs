@@ -0,0 +1,394 @@
+//! Sequence logo rendering to SVG.
+//!
+//! A [`Logo`] renders the columns of a [`FrequencyMatrix`] or of a
+//! [`CountMatrix`] as stacks of letters, where the height of each letter
+//! is proportional to its frequency, and the height of each stack is
+//! either the information content of the column or a constant. Letters are
+//! drawn with SVG `<text>` elements stretched to the size of their box, so
+//! the resulting image does not depend on any external resource.
+
+use std::fmt::Write;
+
+use super::abc::Alphabet;
+use super::abc::AminoAcid;
+use super::abc::Background;
+use super::abc::ComplementableAlphabet;
+use super::abc::Dna;
+use super::abc::Nucleotide;
+use super::abc::Protein;
+use super::abc::Symbol;
+use super::dense::DenseMatrix;
+use super::pwm::known;
+use super::pwm::row_relative_entropy;
+use super::pwm::Count;
+use super::pwm::CountMatrix;
+use super::pwm::FrequencyMatrix;
+
+/// The font size used to draw letters, before scaling.
+const FONT_SIZE: f32 = 100.0;
+/// The height of capital letters relative to the font size.
+const CAP_HEIGHT: f32 = 0.72;
+
+// --- ColorScheme -------------------------------------------------------------
+
+/// The colours used to draw the symbols of an alphabet.
+///
+/// Colours are stored as strings, and can use any syntax supported by SVG,
+/// such as `#109648` or `darkgreen`.
+#[derive(Clone, Debug)]
+pub struct ColorScheme<A: Alphabet> {
+    alphabet: std::marker::PhantomData<A>,
+    colors: Vec<String>,
+}
+
+impl<A: Alphabet> ColorScheme<A> {
+    /// Create a new colour scheme where all symbols have the same colour.
+    pub fn uniform<S: Into<String>>(color: S) -> Self {
+        let color = color.into();
+        Self {
+            alphabet: std::marker::PhantomData,
+            colors: A::symbols().iter().map(|_| color.clone()).collect(),
+        }
+    }
+
+    /// Set the colour of a symbol.
+    pub fn with_color<S: Into<String>>(mut self, symbol: A::Symbol, color: S) -> Self {
+        self.colors[symbol.as_index()] = color.into();
+        self
+    }
+
+    /// Get the colour of a symbol.
+    pub fn color(&self, symbol: A::Symbol) -> &str {
+        &self.colors[symbol.as_index()]
+    }
+}
+
+impl Default for ColorScheme<Dna> {
+    /// The classic nucleotide colours.
+    fn default() -> Self {
+        Self::uniform("#888888")
+            .with_color(Nucleotide::A, "#109648")
+            .with_color(Nucleotide::C, "#255C99")
+            .with_color(Nucleotide::G, "#F7B32B")
+            .with_color(Nucleotide::T, "#D62839")
+    }
+}
+
+impl Default for ColorScheme<Protein> {
+    /// Colours grouping amino acids by their chemical properties.
+    fn default() -> Self {
+        use AminoAcid::*;
+        let groups: [(&[AminoAcid], &str); 5] = [
+            (&[G, S, T, Y, C], "#109648"),
+            (&[Q, N], "#5E239D"),
+            (&[K, R, H], "#255C99"),
+            (&[D, E], "#D62839"),
+            (&[A, V, L, I, P, W, F, M], "#221E22"),
+        ];
+        let mut scheme = Self::uniform("#888888");
+        for (symbols, color) in groups {
+            for &s in symbols {
+                scheme = scheme.with_color(s, color);
+            }
+        }
+        scheme
+    }
+}
+
+// --- Logo --------------------------------------------------------------------
+
+/// The quantity used for the height of the letter stacks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Stacks have a height equal to the information content of the column.
+    Information,
+    /// Stacks have a constant height, and letters show their probability.
+    Probability,
+}
+
+/// A sequence logo renderer.
+#[derive(Clone, Debug)]
+pub struct Logo<A: Alphabet> {
+    mode: Mode,
+    colors: ColorScheme<A>,
+    background: Background<A>,
+    correction: bool,
+    complement: Option<Vec<usize>>,
+    column_width: f32,
+    height: f32,
+}
+
+impl<A: Alphabet> Logo<A> {
+    /// Create a new logo renderer with the given colour scheme.
+    ///
+    /// By default, logos are rendered in information mode against a
+    /// uniform background, without small-sample correction, with columns
+    /// of 30x150 pixels.
+    pub fn new(colors: ColorScheme<A>) -> Self {
+        Self {
+            mode: Mode::Information,
+            colors,
+            background: Background::default(),
+            correction: false,
+            complement: None,
+            column_width: 30.0,
+            height: 150.0,
+        }
+    }
+
+    /// Use the given mode to compute the height of the letter stacks.
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Use the given background to compute the information content.
+    pub fn with_background<B>(mut self, background: B) -> Self
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        self.background = background.into().unwrap_or_default();
+        self
+    }
+
+    /// Enable the small-sample correction of the information content.
+    ///
+    /// The correction from Schneider et al. (1986), `(s - 1) / (2 ln(2) n)`
+    /// for an alphabet of `s` symbols and `n` sequences, is subtracted from
+    /// the information content of each column. It only applies to logos
+    /// rendered from count matrices, where the number of sequences is known.
+    pub fn with_correction(mut self, correction: bool) -> Self {
+        self.correction = correction;
+        self
+    }
+
+    /// Use the given size for each column of the logo, in pixels.
+    pub fn with_size(mut self, column_width: f32, height: f32) -> Self {
+        self.column_width = column_width;
+        self.height = height;
+        self
+    }
+
+    /// Get the maximum information content of a column, in bits.
+    fn max_information(&self) -> f32 {
+        known::<A>(self.background.frequencies())
+            .filter(|&(_, b)| b > 0.0)
+            .map(|(_, b)| -b.log2())
+            .fold(0.0, f32::max)
+    }
+
+    /// Compute the letter stacks for rows of frequencies.
+    ///
+    /// Each stack lists the symbol indices and heights in bits (or in
+    /// probability), sorted by increasing height.
+    fn stacks<'a, I>(&self, rows: I) -> Vec<Vec<(usize, f32)>>
+    where
+        I: IntoIterator<Item = (&'a [f32], Option<f32>)>,
+    {
+        let s = known::<A>(self.background.frequencies()).count() as f32;
+        let mut stacks = rows
+            .into_iter()
+            .map(|(row, n)| {
+                let total = match self.mode {
+                    Mode::Probability => 1.0,
+                    Mode::Information => {
+                        let ic = row_relative_entropy::<A>(row, self.background.frequencies());
+                        let e = match (self.correction, n) {
+                            (true, Some(n)) if n > 0.0 => {
+                                (s - 1.0) / (2.0 * std::f32::consts::LN_2 * n)
+                            }
+                            _ => 0.0,
+                        };
+                        (ic - e).max(0.0)
+                    }
+                };
+                let mut stack = known::<A>(row)
+                    .filter(|&(_, p)| p > 0.0)
+                    .map(|(s, p)| (s.as_index(), p * total))
+                    .collect::<Vec<_>>();
+                stack.sort_by(|x, y| x.1.total_cmp(&y.1));
+                stack
+            })
+            .collect::<Vec<_>>();
+        if let Some(complement) = self.complement.as_ref() {
+            stacks.reverse();
+            for stack in stacks.iter_mut() {
+                for (s, _) in stack.iter_mut() {
+                    *s = complement[*s];
+                }
+            }
+        }
+        stacks
+    }
+
+    /// Render letter stacks to an SVG document.
+    fn render_stacks(&self, stacks: &[Vec<(usize, f32)>]) -> String {
+        let scale = match self.mode {
+            Mode::Probability => self.height,
+            Mode::Information => self.height / self.max_information(),
+        };
+        let width = self.column_width * stacks.len() as f32;
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            width, self.height, width, self.height
+        )
+        .unwrap();
+        let symbols = A::symbols();
+        for (i, stack) in stacks.iter().enumerate() {
+            let x = i as f32 * self.column_width;
+            let mut y = self.height;
+            for &(s, h) in stack.iter() {
+                let h = h * scale;
+                if h <= 0.0 {
+                    continue;
+                }
+                let symbol = symbols
+                    .iter()
+                    .find(|x| x.as_index() == s)
+                    .cloned()
+                    .unwrap_or_default();
+                writeln!(
+                    svg,
+                    concat!(
+                        r#"  <g transform="translate({},{}) scale(1,{})">"#,
+                        r#"<text x="0" y="0" textLength="{}" lengthAdjust="spacingAndGlyphs" "#,
+                        r#"font-family="Arial, Helvetica, sans-serif" font-weight="bold" "#,
+                        r#"font-size="{}" fill="{}">{}</text></g>"#,
+                    ),
+                    x,
+                    y,
+                    h / (CAP_HEIGHT * FONT_SIZE),
+                    self.column_width,
+                    FONT_SIZE,
+                    self.colors.color(symbol),
+                    symbol.as_char(),
+                )
+                .unwrap();
+                y -= h;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Render a logo from a frequency matrix.
+    pub fn render(&self, matrix: &FrequencyMatrix<A>) -> String {
+        let data: &DenseMatrix<f32, A::K> = matrix.as_ref();
+        let stacks = self.stacks(data.iter().map(|row| (row, None)));
+        self.render_stacks(&stacks)
+    }
+
+    /// Render a logo from a count matrix.
+    ///
+    /// The number of sequences used for the small-sample correction is
+    /// the total count of each column.
+    pub fn render_counts<T: Count>(&self, counts: &CountMatrix<A, T>) -> String {
+        let freqs = counts.to_freq(0.0);
+        let data: &DenseMatrix<f32, A::K> = freqs.as_ref();
+        let wildcard = A::default_symbol().as_index();
+        let totals = counts.counts().iter().map(|row| {
+            row.iter()
+                .enumerate()
+                .filter(|&(i, _)| i != wildcard)
+                .map(|(_, &x)| x.to_f32())
+                .sum::<f32>()
+        });
+        let stacks = self.stacks(data.iter().zip(totals).map(|(row, n)| (row, Some(n))));
+        self.render_stacks(&stacks)
+    }
+}
+
+impl<A: ComplementableAlphabet> Logo<A> {
+    /// Render the reverse-complement of the matrices instead of the matrices.
+    pub fn with_reverse_complement(mut self, reverse_complement: bool) -> Self {
+        self.complement = match reverse_complement {
+            false => None,
+            true => {
+                let mut complement = vec![0; A::symbols().len()];
+                for &s in A::symbols() {
+                    complement[s.as_index()] = A::complement(s).as_index();
+                }
+                Some(complement)
+            }
+        };
+        self
+    }
+}
+
+impl<A: Alphabet> Default for Logo<A>
+where
+    ColorScheme<A>: Default,
+{
+    fn default() -> Self {
+        Self::new(ColorScheme::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::seq::EncodedSequence;
+
+    fn build_counts() -> CountMatrix<Dna> {
+        CountMatrix::<Dna>::from_sequences(
+            ["AAC", "AAG", "ACT", "AGA"]
+                .iter()
+                .map(|s| EncodedSequence::encode(s).unwrap()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stacks() {
+        let counts = build_counts();
+        let freqs = counts.to_freq(0.0);
+        let data: &DenseMatrix<f32, <Dna as Alphabet>::K> = freqs.as_ref();
+
+        let logo = Logo::<Dna>::default();
+        let stacks = logo.stacks(data.iter().map(|row| (row, None)));
+        assert_eq!(stacks.len(), 3);
+        assert_eq!(stacks[0], vec![(Nucleotide::A.as_index(), 2.0)]);
+        assert_eq!(stacks[1].last().unwrap(), &(Nucleotide::A.as_index(), 0.25));
+        let ic = freqs.information_content(None);
+        for (stack, ic) in stacks.iter().zip(ic) {
+            let height: f32 = stack.iter().map(|x| x.1).sum();
+            assert!((height - ic).abs() < 1e-6, "{} != {}", height, ic);
+        }
+
+        let logo = Logo::<Dna>::default().with_mode(Mode::Probability);
+        let stacks = logo.stacks(data.iter().map(|row| (row, None)));
+        let total: f32 = stacks[2].iter().map(|x| x.1).sum();
+        assert_eq!(total, 1.0);
+
+        let logo = Logo::<Dna>::default().with_correction(true);
+        let stacks = logo.stacks(data.iter().map(|row| (row, Some(4.0))));
+        let e = 3.0 / (2.0 * std::f32::consts::LN_2 * 4.0);
+        assert!((stacks[0][0].1 - (2.0 - e)).abs() < 1e-6);
+
+        let logo = Logo::<Dna>::default().with_reverse_complement(true);
+        let stacks = logo.stacks(data.iter().map(|row| (row, None)));
+        assert_eq!(stacks[2], vec![(Nucleotide::T.as_index(), 2.0)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let counts = build_counts();
+        let svg = Logo::<Dna>::default().render_counts(&counts);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<text").count(), 4);
+        assert!(svg.contains(r##"fill="#109648">A</text>"##));
+
+        let svg = Logo::<Dna>::default()
+            .with_reverse_complement(true)
+            .render(&counts.to_freq(0.0));
+        assert!(svg.contains(r##"fill="#D62839">T</text>"##));
+
+        let colors = ColorScheme::<Protein>::default();
+        assert_eq!(colors.color(AminoAcid::D), "#D62839");
+        assert_eq!(colors.color(AminoAcid::X), "#888888");
+    }
+}
@@ -1,6 +1,7 @@
 //! Concrete implementations of the sequence scoring pipeline.
 
 use std::ops::Div;
+use std::ops::Range;
 use std::ops::Rem;
 
 pub use self::scores::StripedScores;
@@ -10,6 +11,7 @@ use self::platform::Backend;
 use self::platform::Generic;
 use self::platform::Neon;
 use self::platform::Sse2;
+use self::platform::Table;
 use super::abc::Alphabet;
 use super::abc::Dna;
 use super::abc::Protein;
@@ -142,6 +144,109 @@ pub trait Threshold<C: StrictlyPositive> {
     }
 }
 
+/// Generic trait for summing exponentiated scores in a striped score matrix.
+///
+/// Scores are log-odds in base 2, so the binding affinity of a site is
+/// proportional to `2^score`. Biophysical models such as TRAP consider
+/// the contribution of all sites, and not only of the best one: these
+/// methods sum over all positions of a region, with a temperature `T`
+/// rescaling the scores and a concentration `c` of the transcription
+/// factor. Use `T = 1` and `c = 1` for the plain sums.
+pub trait Affinity<C: StrictlyPositive> {
+    /// Compute the log-sum-exp of the scores of a region.
+    ///
+    /// Returns `T * log2(sum(2^(s / T)))` over the positions of the region,
+    /// which tends to the maximum score as the temperature decreases, or
+    /// negative infinity if the region is empty.
+    ///
+    /// # Panics
+    /// Panics if `temperature` is not strictly positive.
+    fn log_sum_exp(&self, scores: &StripedScores<C>, range: Range<usize>, temperature: f32) -> f32 {
+        assert!(temperature > 0.0, "temperature must be strictly positive");
+        let range = range.start..range.end.min(scores.len());
+        let max = range
+            .clone()
+            .map(|i| scores[i])
+            .fold(f32::NEG_INFINITY, f32::max);
+        if max == f32::NEG_INFINITY {
+            return max;
+        }
+        let sum: f32 = range
+            .map(|i| ((scores[i] - max) / temperature).exp2())
+            .sum();
+        max + temperature * sum.log2()
+    }
+
+    /// Compute the expected occupancy of a region.
+    ///
+    /// Returns `sum(c * 2^(s / T) / (1 + c * 2^(s / T)))` over the
+    /// positions of the region, the expected number of bound sites when
+    /// sites are bound independently.
+    ///
+    /// # Panics
+    /// Panics if `temperature` or `concentration` is not strictly positive.
+    fn occupancy(
+        &self,
+        scores: &StripedScores<C>,
+        range: Range<usize>,
+        temperature: f32,
+        concentration: f32,
+    ) -> f32 {
+        assert!(temperature > 0.0, "temperature must be strictly positive");
+        assert!(
+            concentration > 0.0,
+            "concentration must be strictly positive"
+        );
+        let offset = concentration.log2();
+        (range.start..range.end.min(scores.len()))
+            .map(|i| 1.0 / (1.0 + (-(scores[i] / temperature + offset)).exp2()))
+            .sum()
+    }
+
+    /// Compute the log-sum-exp of the scores of a region on both strands.
+    ///
+    /// The range is given in positions of the direct strand, and the
+    /// scores of the reverse strand are expected to be computed on the
+    /// reverse complement of the sequence.
+    fn log_sum_exp_strands(
+        &self,
+        direct: &StripedScores<C>,
+        reverse: &StripedScores<C>,
+        range: Range<usize>,
+        temperature: f32,
+    ) -> f32 {
+        let end = range.end.min(direct.len());
+        let rc = reverse.len().saturating_sub(end)..reverse.len().saturating_sub(range.start);
+        let x = self.log_sum_exp(direct, range, temperature);
+        let y = self.log_sum_exp(reverse, rc, temperature);
+        let max = x.max(y);
+        if max == f32::NEG_INFINITY {
+            return max;
+        }
+        max + temperature
+            * (((x - max) / temperature).exp2() + ((y - max) / temperature).exp2()).log2()
+    }
+
+    /// Compute the expected occupancy of a region on both strands.
+    ///
+    /// The range is given in positions of the direct strand, and the
+    /// scores of the reverse strand are expected to be computed on the
+    /// reverse complement of the sequence.
+    fn occupancy_strands(
+        &self,
+        direct: &StripedScores<C>,
+        reverse: &StripedScores<C>,
+        range: Range<usize>,
+        temperature: f32,
+        concentration: f32,
+    ) -> f32 {
+        let end = range.end.min(direct.len());
+        let rc = reverse.len().saturating_sub(end)..reverse.len().saturating_sub(range.start);
+        self.occupancy(direct, range, temperature, concentration)
+            + self.occupancy(reverse, rc, temperature, concentration)
+    }
+}
+
 // --- Pipeline ----------------------------------------------------------------
 
 /// Wrapper implementing score computation for different platforms.
@@ -171,6 +276,43 @@ impl<A: Alphabet, C: StrictlyPositive> BestPosition<C> for Pipeline<A, Generic>
 
 impl<A: Alphabet, C: StrictlyPositive> Threshold<C> for Pipeline<A, Generic> {}
 
+impl<A: Alphabet, C: StrictlyPositive> Affinity<C> for Pipeline<A, Generic> {}
+
+// --- Table pipeline ----------------------------------------------------------
+
+impl Pipeline<Dna, Table> {
+    /// Create a new table-driven pipeline.
+    ///
+    /// Scores are summed block by block rather than column by column, so
+    /// they may differ from the other pipelines in the last bits, and a
+    /// position scoring exactly at a threshold may be reported by one
+    /// pipeline and not by another.
+    pub const fn table() -> Self {
+        Self {
+            alphabet: std::marker::PhantomData,
+            backend: std::marker::PhantomData,
+        }
+    }
+}
+
+impl Encode<Dna> for Pipeline<Dna, Table> {}
+
+impl<C: StrictlyPositive> Score<Dna, C> for Pipeline<Dna, Table> {
+    fn score_into<S, M>(&self, seq: S, pssm: M, scores: &mut StripedScores<C>)
+    where
+        S: AsRef<StripedSequence<Dna, C>>,
+        M: AsRef<ScoringMatrix<Dna>>,
+    {
+        Table::score_into(seq, pssm, scores)
+    }
+}
+
+impl<C: StrictlyPositive> BestPosition<C> for Pipeline<Dna, Table> {}
+
+impl<C: StrictlyPositive> Threshold<C> for Pipeline<Dna, Table> {}
+
+impl<C: StrictlyPositive> Affinity<C> for Pipeline<Dna, Table> {}
+
 // --- SSE2 pipeline -----------------------------------------------------------
 
 impl<A: Alphabet> Pipeline<A, Sse2> {
@@ -229,6 +371,28 @@ where
     }
 }
 
+impl<A, C> Affinity<C> for Pipeline<A, Sse2>
+where
+    A: Alphabet,
+    C: StrictlyPositive + Rem<U16> + Div<U16>,
+    <C as Rem<U16>>::Output: Zero,
+    <C as Div<U16>>::Output: Unsigned,
+{
+    fn log_sum_exp(&self, scores: &StripedScores<C>, range: Range<usize>, temperature: f32) -> f32 {
+        Sse2::log_sum_exp(scores, range, temperature)
+    }
+
+    fn occupancy(
+        &self,
+        scores: &StripedScores<C>,
+        range: Range<usize>,
+        temperature: f32,
+        concentration: f32,
+    ) -> f32 {
+        Sse2::occupancy(scores, range, temperature, concentration)
+    }
+}
+
 // --- AVX2 pipeline -----------------------------------------------------------
 
 impl<A: Alphabet> Pipeline<A, Avx2> {
@@ -296,6 +460,27 @@ impl<A: Alphabet> Threshold<<Avx2 as Backend>::LANES> for Pipeline<A, Avx2> {
     }
 }
 
+impl<A: Alphabet> Affinity<<Avx2 as Backend>::LANES> for Pipeline<A, Avx2> {
+    fn log_sum_exp(
+        &self,
+        scores: &StripedScores<<Avx2 as Backend>::LANES>,
+        range: Range<usize>,
+        temperature: f32,
+    ) -> f32 {
+        Avx2::log_sum_exp(scores, range, temperature)
+    }
+
+    fn occupancy(
+        &self,
+        scores: &StripedScores<<Avx2 as Backend>::LANES>,
+        range: Range<usize>,
+        temperature: f32,
+        concentration: f32,
+    ) -> f32 {
+        Avx2::occupancy(scores, range, temperature, concentration)
+    }
+}
+
 // --- NEON pipeline -----------------------------------------------------------
 
 impl<A: Alphabet> Pipeline<A, Neon> {
@@ -348,3 +533,12 @@ where
     <C as Div<U16>>::Output: Unsigned,
 {
 }
+
+impl<A, C> Affinity<C> for Pipeline<A, Neon>
+where
+    A: Alphabet,
+    C: StrictlyPositive + Rem<U16> + Div<U16>,
+    <C as Rem<U16>>::Output: Zero,
+    <C as Div<U16>>::Output: Unsigned,
+{
+}
@@ -4,6 +4,7 @@
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
+use std::ops::Range;
 
 use typenum::consts::U32;
 use typenum::consts::U5;
@@ -437,6 +438,123 @@ unsafe fn threshold_avx2(
     }
 }
 
+/// The coefficients of the Taylor series of `2^x` around zero.
+const EXP2_COEFFICIENTS: [f32; 7] = [
+    1.525_273_4e-5,
+    1.540_353_1e-4,
+    1.333_355_8e-3,
+    9.618_129e-3,
+    5.550_411e-2,
+    2.402_265e-1,
+    6.931_472e-1,
+];
+
+/// Compute `2^x` for packed single-precision values.
+///
+/// The input is clamped to `[-126, 126]` and rounded to the nearest
+/// integer `i`, and `2^(x - i)` is approximated with a polynomial, giving
+/// a relative error below `1e-6`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn exp2_avx2(x: __m256) -> __m256 {
+    // NOTE: `_mm256_max_ps` returns the second operand when the first is NaN
+    let x = _mm256_min_ps(
+        _mm256_max_ps(x, _mm256_set1_ps(-126.0)),
+        _mm256_set1_ps(126.0),
+    );
+    let i = _mm256_cvtps_epi32(x);
+    let f = _mm256_sub_ps(x, _mm256_cvtepi32_ps(i));
+    let mut p = _mm256_set1_ps(EXP2_COEFFICIENTS[0]);
+    for &c in &EXP2_COEFFICIENTS[1..] {
+        p = _mm256_add_ps(_mm256_mul_ps(p, f), _mm256_set1_ps(c));
+    }
+    p = _mm256_add_ps(_mm256_mul_ps(p, f), _mm256_set1_ps(1.0));
+    let e = _mm256_slli_epi32(_mm256_add_epi32(i, _mm256_set1_epi32(127)), 23);
+    _mm256_mul_ps(p, _mm256_castsi256_ps(e))
+}
+
+/// Compute a mask of the positions inside a range for 8 consecutive columns.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn row_bounds_avx2(offset: usize, rows: usize, range: &Range<usize>) -> (__m256i, __m256i) {
+    let mut lo = [0i32; 8];
+    let mut hi = [0i32; 8];
+    for k in 0..8 {
+        let col = offset + k;
+        lo[k] = range.start.saturating_sub(col * rows).min(rows) as i32;
+        hi[k] = range.end.saturating_sub(col * rows).min(rows) as i32;
+    }
+    (
+        _mm256_loadu_si256(lo.as_ptr() as *const _),
+        _mm256_loadu_si256(hi.as_ptr() as *const _),
+    )
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn max_avx2(scores: &StripedScores<<Avx2 as Backend>::LANES>, range: Range<usize>) -> f32 {
+    let data = scores.matrix();
+    let mut max = _mm256_set1_ps(f32::NEG_INFINITY);
+    for offset in (0..<Avx2 as Backend>::LANES::USIZE).step_by(8) {
+        let (lo, hi) = row_bounds_avx2(offset, data.rows(), &range);
+        let mut dataptr = data[0].as_ptr().add(offset);
+        for i in 0..data.rows() {
+            let index = _mm256_set1_epi32(i as i32);
+            // mask rows outside of the range
+            let mask = _mm256_castsi256_ps(_mm256_andnot_si256(
+                _mm256_cmpgt_epi32(lo, index),
+                _mm256_cmpgt_epi32(hi, index),
+            ));
+            let r = _mm256_load_ps(dataptr);
+            let r = _mm256_blendv_ps(_mm256_set1_ps(f32::NEG_INFINITY), r, mask);
+            max = _mm256_max_ps(max, r);
+            dataptr = dataptr.add(data.stride());
+        }
+    }
+    let mut x = [0.0f32; 8];
+    _mm256_storeu_ps(x.as_mut_ptr(), max);
+    x.into_iter().fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// Sum `2^(a * s + b)`, or `1 / (1 + 2^(a * s + b))` if `logistic` is set.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn sum_exp2_avx2(
+    scores: &StripedScores<<Avx2 as Backend>::LANES>,
+    range: Range<usize>,
+    a: f32,
+    b: f32,
+    logistic: bool,
+) -> f32 {
+    let data = scores.matrix();
+    let va = _mm256_set1_ps(a);
+    let vb = _mm256_set1_ps(b);
+    let one = _mm256_set1_ps(1.0);
+    let mut sum = _mm256_setzero_ps();
+    for offset in (0..<Avx2 as Backend>::LANES::USIZE).step_by(8) {
+        let (lo, hi) = row_bounds_avx2(offset, data.rows(), &range);
+        let mut dataptr = data[0].as_ptr().add(offset);
+        for i in 0..data.rows() {
+            let index = _mm256_set1_epi32(i as i32);
+            // mask rows outside of the range
+            let mask = _mm256_castsi256_ps(_mm256_andnot_si256(
+                _mm256_cmpgt_epi32(lo, index),
+                _mm256_cmpgt_epi32(hi, index),
+            ));
+            let r = _mm256_load_ps(dataptr);
+            let mut e = exp2_avx2(_mm256_add_ps(_mm256_mul_ps(r, va), vb));
+            if logistic {
+                e = _mm256_div_ps(one, _mm256_add_ps(one, e));
+            }
+            sum = _mm256_add_ps(sum, _mm256_and_ps(mask, e));
+            dataptr = dataptr.add(data.stride());
+        }
+    }
+    let mut x = [0.0f32; 8];
+    _mm256_storeu_ps(x.as_mut_ptr(), sum);
+    x.into_iter().sum()
+}
+
 /// Intel 256-bit vector implementation, for 32 elements column width.
 impl Avx2 {
     #[allow(unused)]
@@ -537,4 +655,58 @@ impl Avx2 {
         #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
         panic!("attempting to run AVX2 code on a non-x86 host")
     }
+
+    #[allow(unused)]
+    pub fn log_sum_exp(
+        scores: &StripedScores<<Avx2 as Backend>::LANES>,
+        range: Range<usize>,
+        temperature: f32,
+    ) -> f32 {
+        assert!(temperature > 0.0, "temperature must be strictly positive");
+        let range = range.start..range.end.min(scores.len());
+        if range.is_empty() {
+            return f32::NEG_INFINITY;
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        unsafe {
+            let max = max_avx2(scores, range.clone());
+            if max == f32::NEG_INFINITY {
+                return max;
+            }
+            let sum = sum_exp2_avx2(scores, range, 1.0 / temperature, -max / temperature, false);
+            max + temperature * sum.log2()
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        panic!("attempting to run AVX2 code on a non-x86 host")
+    }
+
+    #[allow(unused)]
+    pub fn occupancy(
+        scores: &StripedScores<<Avx2 as Backend>::LANES>,
+        range: Range<usize>,
+        temperature: f32,
+        concentration: f32,
+    ) -> f32 {
+        assert!(temperature > 0.0, "temperature must be strictly positive");
+        assert!(
+            concentration > 0.0,
+            "concentration must be strictly positive"
+        );
+        let range = range.start..range.end.min(scores.len());
+        if range.is_empty() {
+            return 0.0;
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        unsafe {
+            sum_exp2_avx2(
+                scores,
+                range,
+                -1.0 / temperature,
+                -concentration.log2(),
+                true,
+            )
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        panic!("attempting to run AVX2 code on a non-x86 host")
+    }
 }
@@ -4,11 +4,13 @@ mod avx2;
 mod generic;
 mod neon;
 mod sse2;
+mod table;
 
 pub use self::avx2::Avx2;
 pub use self::generic::Generic;
 pub use self::neon::Neon;
 pub use self::sse2::Sse2;
+pub use self::table::Table;
 
 use typenum::marker_traits::NonZero;
 use typenum::marker_traits::Unsigned;
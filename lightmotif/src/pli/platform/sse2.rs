@@ -5,6 +5,7 @@ use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 use std::ops::Div;
+use std::ops::Range;
 use std::ops::Rem;
 
 use typenum::consts::U16;
@@ -296,6 +297,136 @@ where
     }
 }
 
+/// The coefficients of the Taylor series of `2^x` around zero.
+const EXP2_COEFFICIENTS: [f32; 7] = [
+    1.525_273_4e-5,
+    1.540_353_1e-4,
+    1.333_355_8e-3,
+    9.618_129e-3,
+    5.550_411e-2,
+    2.402_265e-1,
+    6.931_472e-1,
+];
+
+/// Compute `2^x` for packed single-precision values.
+///
+/// The input is clamped to `[-126, 126]` and rounded to the nearest
+/// integer `i`, and `2^(x - i)` is approximated with a polynomial, giving
+/// a relative error below `1e-6`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn exp2_sse2(x: __m128) -> __m128 {
+    // NOTE: `_mm_max_ps` returns the second operand when the first is NaN
+    let x = _mm_min_ps(_mm_max_ps(x, _mm_set1_ps(-126.0)), _mm_set1_ps(126.0));
+    let i = _mm_cvtps_epi32(x);
+    let f = _mm_sub_ps(x, _mm_cvtepi32_ps(i));
+    let mut p = _mm_set1_ps(EXP2_COEFFICIENTS[0]);
+    for &c in &EXP2_COEFFICIENTS[1..] {
+        p = _mm_add_ps(_mm_mul_ps(p, f), _mm_set1_ps(c));
+    }
+    p = _mm_add_ps(_mm_mul_ps(p, f), _mm_set1_ps(1.0));
+    let e = _mm_slli_epi32(_mm_add_epi32(i, _mm_set1_epi32(127)), 23);
+    _mm_mul_ps(p, _mm_castsi128_ps(e))
+}
+
+/// Compute the bounds of the rows inside a range for 4 consecutive columns.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn row_bounds_sse2(offset: usize, rows: usize, range: &Range<usize>) -> (__m128i, __m128i) {
+    let bound = |col: usize, x: usize| x.saturating_sub(col * rows).min(rows) as i32;
+    let lo = _mm_setr_epi32(
+        bound(offset, range.start),
+        bound(offset + 1, range.start),
+        bound(offset + 2, range.start),
+        bound(offset + 3, range.start),
+    );
+    let hi = _mm_setr_epi32(
+        bound(offset, range.end),
+        bound(offset + 1, range.end),
+        bound(offset + 2, range.end),
+        bound(offset + 3, range.end),
+    );
+    (lo, hi)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn max_sse2<C>(scores: &StripedScores<C>, range: Range<usize>) -> f32
+where
+    C: StrictlyPositive + Rem<U16> + Div<U16>,
+    <C as Rem<U16>>::Output: Zero,
+    <C as Div<U16>>::Output: Unsigned,
+{
+    let data = scores.matrix();
+    let mut max = _mm_set1_ps(f32::NEG_INFINITY);
+    for offset in (0..C::USIZE).step_by(4) {
+        let (lo, hi) = row_bounds_sse2(offset, data.rows(), &range);
+        let mut dataptr = data[0].as_ptr().add(offset);
+        for i in 0..data.rows() {
+            let index = _mm_set1_epi32(i as i32);
+            // mask rows outside of the range
+            let mask = _mm_castsi128_ps(_mm_andnot_si128(
+                _mm_cmpgt_epi32(lo, index),
+                _mm_cmpgt_epi32(hi, index),
+            ));
+            let r = _mm_load_ps(dataptr);
+            let r = _mm_or_ps(
+                _mm_and_ps(mask, r),
+                _mm_andnot_ps(mask, _mm_set1_ps(f32::NEG_INFINITY)),
+            );
+            max = _mm_max_ps(max, r);
+            dataptr = dataptr.add(data.stride());
+        }
+    }
+    let mut x = [0.0f32; 4];
+    _mm_storeu_ps(x.as_mut_ptr(), max);
+    x.into_iter().fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// Sum `2^(a * s + b)`, or `1 / (1 + 2^(a * s + b))` if `logistic` is set.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn sum_exp2_sse2<C>(
+    scores: &StripedScores<C>,
+    range: Range<usize>,
+    a: f32,
+    b: f32,
+    logistic: bool,
+) -> f32
+where
+    C: StrictlyPositive + Rem<U16> + Div<U16>,
+    <C as Rem<U16>>::Output: Zero,
+    <C as Div<U16>>::Output: Unsigned,
+{
+    let data = scores.matrix();
+    let va = _mm_set1_ps(a);
+    let vb = _mm_set1_ps(b);
+    let one = _mm_set1_ps(1.0);
+    let mut sum = _mm_setzero_ps();
+    for offset in (0..C::USIZE).step_by(4) {
+        let (lo, hi) = row_bounds_sse2(offset, data.rows(), &range);
+        let mut dataptr = data[0].as_ptr().add(offset);
+        for i in 0..data.rows() {
+            let index = _mm_set1_epi32(i as i32);
+            // mask rows outside of the range
+            let mask = _mm_castsi128_ps(_mm_andnot_si128(
+                _mm_cmpgt_epi32(lo, index),
+                _mm_cmpgt_epi32(hi, index),
+            ));
+            let r = _mm_load_ps(dataptr);
+            let mut e = exp2_sse2(_mm_add_ps(_mm_mul_ps(r, va), vb));
+            if logistic {
+                e = _mm_div_ps(one, _mm_add_ps(one, e));
+            }
+            sum = _mm_add_ps(sum, _mm_and_ps(mask, e));
+            dataptr = dataptr.add(data.stride());
+        }
+    }
+    let mut x = [0.0f32; 4];
+    _mm_storeu_ps(x.as_mut_ptr(), sum);
+    x.into_iter().sum()
+}
+
 impl Sse2 {
     #[allow(unused)]
     pub fn score_into<A, C, S, M>(seq: S, pssm: M, scores: &mut StripedScores<C>)
@@ -355,4 +486,64 @@ impl Sse2 {
         #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
         panic!("attempting to run SSE2 code on a non-x86 host")
     }
+
+    #[allow(unused)]
+    pub fn log_sum_exp<C>(scores: &StripedScores<C>, range: Range<usize>, temperature: f32) -> f32
+    where
+        C: StrictlyPositive + Rem<U16> + Div<U16>,
+        <C as Rem<U16>>::Output: Zero,
+        <C as Div<U16>>::Output: Unsigned,
+    {
+        assert!(temperature > 0.0, "temperature must be strictly positive");
+        let range = range.start..range.end.min(scores.len());
+        if range.is_empty() {
+            return f32::NEG_INFINITY;
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        unsafe {
+            let max = max_sse2(scores, range.clone());
+            if max == f32::NEG_INFINITY {
+                return max;
+            }
+            let sum = sum_exp2_sse2(scores, range, 1.0 / temperature, -max / temperature, false);
+            max + temperature * sum.log2()
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        panic!("attempting to run SSE2 code on a non-x86 host")
+    }
+
+    #[allow(unused)]
+    pub fn occupancy<C>(
+        scores: &StripedScores<C>,
+        range: Range<usize>,
+        temperature: f32,
+        concentration: f32,
+    ) -> f32
+    where
+        C: StrictlyPositive + Rem<U16> + Div<U16>,
+        <C as Rem<U16>>::Output: Zero,
+        <C as Div<U16>>::Output: Unsigned,
+    {
+        assert!(temperature > 0.0, "temperature must be strictly positive");
+        assert!(
+            concentration > 0.0,
+            "concentration must be strictly positive"
+        );
+        let range = range.start..range.end.min(scores.len());
+        if range.is_empty() {
+            return 0.0;
+        }
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        unsafe {
+            sum_exp2_sse2(
+                scores,
+                range,
+                -1.0 / temperature,
+                -concentration.log2(),
+                true,
+            )
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        panic!("attempting to run SSE2 code on a non-x86 host")
+    }
 }
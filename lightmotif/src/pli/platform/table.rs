@@ -0,0 +1,138 @@
+//! Table-driven implementation for DNA, scoring k-mers by lookup.
+
+use typenum::consts::U1;
+
+use super::Backend;
+use crate::abc::Dna;
+use crate::abc::Symbol;
+use crate::num::StrictlyPositive;
+use crate::pli::scores::StripedScores;
+use crate::pwm::ScoringMatrix;
+use crate::seq::StripedSequence;
+
+/// A marker type for the table-driven implementation of the pipeline.
+///
+/// The scoring matrix is split into blocks of at most [`Table::BLOCK`]
+/// columns, and the score of every possible k-mer is precomputed for each
+/// block. Windows are then scored with one table lookup per block instead
+/// of one addition per column, using rolling 2-bit codes of the k-mers of
+/// the sequence. Blocks overlapping an unknown nucleotide are scored
+/// column by column.
+///
+/// Scores are summed block by block, and may therefore differ from the
+/// scores of other implementations by rounding errors.
+#[derive(Clone, Debug, Default)]
+pub struct Table;
+
+impl Backend for Table {
+    type LANES = U1;
+}
+
+/// The code of k-mers containing an unknown nucleotide.
+const INVALID: u32 = u32::MAX;
+
+/// A block of consecutive columns of a scoring matrix.
+struct Block {
+    start: usize,
+    size: usize,
+    scores: Vec<f32>,
+}
+
+impl Block {
+    /// Precompute the scores of all k-mers for the given columns.
+    fn new(pssm: &ScoringMatrix<Dna>, start: usize, size: usize) -> Self {
+        let weights = pssm.weights();
+        let scores = (0..1usize << (2 * size))
+            .map(|code| {
+                let mut score = 0.0;
+                for t in 0..size {
+                    let x = (code >> (2 * (size - t - 1))) & 0b11;
+                    score += weights[start + t][x];
+                }
+                score
+            })
+            .collect();
+        Self {
+            start,
+            size,
+            scores,
+        }
+    }
+}
+
+/// Compute the rolling 2-bit codes of all k-mers of a striped sequence.
+fn kmer_codes<C: StrictlyPositive>(seq: &StripedSequence<Dna, C>, k: usize) -> Vec<u32> {
+    let rows = seq.data.rows() - seq.wrap;
+    let mask = (1u32 << (2 * k)) - 1;
+    let mut codes = vec![INVALID; (seq.length + 1).saturating_sub(k)];
+    let mut code = 0;
+    let mut valid = 0;
+    for offset in 0..seq.length {
+        let x = seq.data[offset % rows][offset / rows].as_index();
+        if x < 4 {
+            code = ((code << 2) | x as u32) & mask;
+            valid += 1;
+        } else {
+            valid = 0;
+        }
+        if valid >= k {
+            codes[offset + 1 - k] = code;
+        }
+    }
+    codes
+}
+
+impl Table {
+    /// The maximum number of columns per block.
+    pub const BLOCK: usize = 4;
+
+    /// Compute the PSSM scores into the given striped score matrix.
+    pub fn score_into<C, S, M>(seq: S, pssm: M, scores: &mut StripedScores<C>)
+    where
+        C: StrictlyPositive,
+        S: AsRef<StripedSequence<Dna, C>>,
+        M: AsRef<ScoringMatrix<Dna>>,
+    {
+        let seq = seq.as_ref();
+        let pssm = pssm.as_ref();
+        let seq_rows = seq.data.rows() - seq.wrap;
+        let length = seq.length - pssm.len() + 1;
+        scores.resize(length, seq_rows);
+
+        // split the matrix into blocks of (almost) equal sizes
+        let n = pssm.len().div_ceil(Self::BLOCK);
+        let mut blocks = Vec::with_capacity(n);
+        let mut start = 0;
+        for b in 0..n {
+            let size = (pssm.len() - start) / (n - b);
+            blocks.push(Block::new(pssm, start, size));
+            start += size;
+        }
+        let mut codes = [Vec::new(), Vec::new()];
+        let small = pssm.len() / n.max(1);
+        for (i, c) in codes.iter_mut().enumerate() {
+            if small + i > 0 && blocks.iter().any(|b| b.size == small + i) {
+                *c = kmer_codes(seq, small + i);
+            }
+        }
+
+        let weights = pssm.weights();
+        let result = scores.matrix_mut();
+        for i in 0..length {
+            let mut score = 0.0;
+            for block in blocks.iter() {
+                let code = codes[block.size - small][i + block.start];
+                if code != INVALID {
+                    score += block.scores[code as usize];
+                } else {
+                    for t in block.start..block.start + block.size {
+                        let offset = i + t;
+                        let x = seq.data[offset % seq_rows][offset / seq_rows];
+                        score += weights[t][x.as_index()];
+                    }
+                }
+            }
+            result[i % seq_rows][i / seq_rows] = score;
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//! Pseudocount strategies used to estimate frequencies from counts.
+//!
+//! A [`Prior`] computes the pseudocounts to add to each column of a
+//! [`CountMatrix`] before normalizing it into a [`FrequencyMatrix`]. A
+//! constant pseudocount, or a [`Pseudocounts`] array, can be used for the
+//! simple case where every column receives the same pseudocounts, while
+//! the strategies of this module adapt the pseudocounts to the observed
+//! counts of each column.
+//!
+//! [`CountMatrix`]: crate::pwm::CountMatrix
+//! [`FrequencyMatrix`]: crate::pwm::FrequencyMatrix
+
+use generic_array::GenericArray;
+
+use super::abc::Alphabet;
+use super::abc::Background;
+use super::abc::Protein;
+use super::abc::Pseudocounts;
+use super::abc::Symbol;
+use super::stats::ln_gamma;
+
+/// Get the total count of a column, excluding the wildcard symbol.
+fn total<A: Alphabet>(counts: &[f32]) -> f32 {
+    let wildcard = A::default_symbol().as_index();
+    counts
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != wildcard)
+        .map(|(_, &x)| x)
+        .sum()
+}
+
+// --- Prior -------------------------------------------------------------------
+
+/// A strategy to compute the pseudocounts of a motif column.
+pub trait Prior<A: Alphabet> {
+    /// Compute the pseudocounts to add to a column with the given counts.
+    ///
+    /// The `counts` slice contains the counts of every symbol of the
+    /// alphabet, indexed by [`Symbol::as_index`].
+    fn pseudocounts(&self, counts: &[f32]) -> Pseudocounts<A>;
+}
+
+impl<A: Alphabet> Prior<A> for Pseudocounts<A> {
+    fn pseudocounts(&self, _counts: &[f32]) -> Pseudocounts<A> {
+        self.clone()
+    }
+}
+
+impl<A: Alphabet> Prior<A> for f32 {
+    fn pseudocounts(&self, _counts: &[f32]) -> Pseudocounts<A> {
+        Pseudocounts::from(*self)
+    }
+}
+
+impl<A: Alphabet> Prior<A> for GenericArray<f32, A::K> {
+    fn pseudocounts(&self, _counts: &[f32]) -> Pseudocounts<A> {
+        Pseudocounts::from(self.clone())
+    }
+}
+
+impl<A: Alphabet, P: Prior<A>> Prior<A> for &P {
+    fn pseudocounts(&self, counts: &[f32]) -> Pseudocounts<A> {
+        (*self).pseudocounts(counts)
+    }
+}
+
+// --- BackgroundPrior ---------------------------------------------------------
+
+/// Pseudocounts proportional to the background frequencies.
+///
+/// Each symbol `x` receives a pseudocount of `alpha * bg[x]`, so that a
+/// total of `alpha` pseudocounts is distributed according to the background.
+#[derive(Clone, Debug)]
+pub struct BackgroundPrior<A: Alphabet> {
+    alpha: f32,
+    background: Background<A>,
+}
+
+impl<A: Alphabet> BackgroundPrior<A> {
+    /// Create a new background prior with the given total pseudocount.
+    pub fn new<B>(alpha: f32, background: B) -> Self
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        Self {
+            alpha,
+            background: background.into().unwrap_or_default(),
+        }
+    }
+}
+
+impl<A: Alphabet> Prior<A> for BackgroundPrior<A> {
+    fn pseudocounts(&self, _counts: &[f32]) -> Pseudocounts<A> {
+        let counts: GenericArray<f32, A::K> = self
+            .background
+            .frequencies()
+            .iter()
+            .map(|&f| self.alpha * f)
+            .collect();
+        Pseudocounts::from(counts)
+    }
+}
+
+// --- SquareRootPrior ---------------------------------------------------------
+
+/// Pseudocounts scaling with the square root of the number of sequences.
+///
+/// Each symbol `x` of a column with `N` observations receives a
+/// pseudocount of `sqrt(N) * bg[x]`, so that the relative influence of
+/// the prior decreases as more sequences are observed.
+#[derive(Clone, Debug)]
+pub struct SquareRootPrior<A: Alphabet> {
+    background: Background<A>,
+}
+
+impl<A: Alphabet> SquareRootPrior<A> {
+    /// Create a new square-root prior with the given background.
+    pub fn new<B>(background: B) -> Self
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        Self {
+            background: background.into().unwrap_or_default(),
+        }
+    }
+}
+
+impl<A: Alphabet> Default for SquareRootPrior<A> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl<A: Alphabet> Prior<A> for SquareRootPrior<A> {
+    fn pseudocounts(&self, counts: &[f32]) -> Pseudocounts<A> {
+        let alpha = total::<A>(counts).sqrt();
+        BackgroundPrior {
+            alpha,
+            background: self.background.clone(),
+        }
+        .pseudocounts(counts)
+    }
+}
+
+// --- SubstitutionPrior -------------------------------------------------------
+
+/// The symbols of the BLOSUM62 matrix, in the order of its rows and columns.
+const BLOSUM62_SYMBOLS: &[u8; 20] = b"ARNDCQEGHILKMFPSTWYV";
+
+/// The background frequencies of the BLOSUM62 matrix.
+const BLOSUM62_BACKGROUND: [f32; 20] = [
+    0.074, 0.052, 0.045, 0.054, 0.025, 0.034, 0.054, 0.074, 0.026, 0.068, 0.099, 0.058, 0.025,
+    0.047, 0.039, 0.057, 0.051, 0.013, 0.032, 0.073,
+];
+
+/// The BLOSUM62 substitution scores, in half-bits.
+#[rustfmt::skip]
+const BLOSUM62: [[i8; 20]; 20] = [
+    //A   R   N   D   C   Q   E   G   H   I   L   K   M   F   P   S   T   W   Y   V
+    [ 4, -1, -2, -2,  0, -1, -1,  0, -2, -1, -1, -1, -1, -2, -1,  1,  0, -3, -2,  0], // A
+    [-1,  5,  0, -2, -3,  1,  0, -2,  0, -3, -2,  2, -1, -3, -2, -1, -1, -3, -2, -3], // R
+    [-2,  0,  6,  1, -3,  0,  0,  0,  1, -3, -3,  0, -2, -3, -2,  1,  0, -4, -2, -3], // N
+    [-2, -2,  1,  6, -3,  0,  2, -1, -1, -3, -4, -1, -3, -3, -1,  0, -1, -4, -3, -3], // D
+    [ 0, -3, -3, -3,  9, -3, -4, -3, -3, -1, -1, -3, -1, -2, -3, -1, -1, -2, -2, -1], // C
+    [-1,  1,  0,  0, -3,  5,  2, -2,  0, -3, -2,  1,  0, -3, -1,  0, -1, -2, -1, -2], // Q
+    [-1,  0,  0,  2, -4,  2,  5, -2,  0, -3, -3,  1, -2, -3, -1,  0, -1, -3, -2, -2], // E
+    [ 0, -2,  0, -1, -3, -2, -2,  6, -2, -4, -4, -2, -3, -3, -2,  0, -2, -2, -3, -3], // G
+    [-2,  0,  1, -1, -3,  0,  0, -2,  8, -3, -3, -1, -2, -1, -2, -1, -2, -2,  2, -3], // H
+    [-1, -3, -3, -3, -1, -3, -3, -4, -3,  4,  2, -3,  1,  0, -3, -2, -1, -3, -1,  3], // I
+    [-1, -2, -3, -4, -1, -2, -3, -4, -3,  2,  4, -2,  2,  0, -3, -2, -1, -2, -1,  1], // L
+    [-1,  2,  0, -1, -3,  1,  1, -2, -1, -3, -2,  5, -1, -3, -1,  0, -1, -3, -2, -2], // K
+    [-1, -1, -2, -3, -1,  0, -2, -3, -2,  1,  2, -1,  5,  0, -2, -1, -1, -1, -1,  1], // M
+    [-2, -3, -3, -3, -2, -3, -3, -3, -1,  0,  0, -3,  0,  6, -4, -2, -2,  1,  3, -1], // F
+    [-1, -2, -2, -1, -3, -1, -1, -2, -2, -3, -3, -1, -2, -4,  7, -1, -1, -4, -3, -2], // P
+    [ 1, -1,  1,  0, -1,  0,  0,  0, -1, -2, -2,  0, -1, -2, -1,  4,  1, -3, -2, -2], // S
+    [ 0, -1,  0, -1, -1, -1, -1, -2, -2, -1, -1, -1, -1, -2, -1,  1,  5, -2, -2,  0], // T
+    [-3, -3, -4, -4, -2, -2, -3, -2, -2, -3, -2, -3, -1,  1, -4, -3, -2, 11,  2, -3], // W
+    [-2, -2, -2, -3, -2, -1, -2, -3,  2, -1, -1, -2, -1,  3, -3, -2, -2,  2,  7, -1], // Y
+    [ 0, -3, -3, -3, -1, -2, -2, -3, -3,  3,  1, -2,  1, -1, -2, -2,  0, -3, -1,  4], // V
+];
+
+/// Substitution-matrix-based pseudocounts from Henikoff & Henikoff (1996).
+///
+/// The pseudocounts of a column are distributed according to the
+/// residues that are likely to substitute the observed residues: the
+/// pseudocount of residue `a` is proportional to the sum over observed
+/// residues `b` of `f(b) * q(a | b)`, where `f(b)` is the observed
+/// frequency of `b` and `q(a | b)` is the conditional substitution
+/// probability. The total pseudocount is `m * R`, where `R` is the number
+/// of distinct residues observed in the column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubstitutionPrior {
+    /// The conditional probabilities `q(a | b)`, stored as `[b][a]`.
+    conditional: Vec<[f32; 20]>,
+    /// The multiplier of the number of distinct residues.
+    m: f32,
+}
+
+impl SubstitutionPrior {
+    /// Create a new prior from the BLOSUM62 matrix, with `m = 5`.
+    ///
+    /// The target frequencies of the matrix are reconstructed from the
+    /// BLOSUM62 scores and background frequencies, as the scores are
+    /// log-odds ratios in half-bits.
+    pub fn blosum62() -> Self {
+        let index = |c: u8| {
+            <Protein as Alphabet>::Symbol::from_ascii(c)
+                .unwrap()
+                .as_index()
+        };
+        let mut conditional = vec![[0.0; 20]; 20];
+        for (i, &b) in BLOSUM62_SYMBOLS.iter().enumerate() {
+            let row = &mut conditional[index(b)];
+            for (j, &a) in BLOSUM62_SYMBOLS.iter().enumerate() {
+                let odds = 2f32.powf(BLOSUM62[i][j] as f32 / 2.0);
+                row[index(a)] = BLOSUM62_BACKGROUND[j] * odds;
+            }
+            let total: f32 = row.iter().sum();
+            for x in row.iter_mut() {
+                *x /= total;
+            }
+        }
+        Self {
+            conditional,
+            m: 5.0,
+        }
+    }
+
+    /// Use a different multiplier for the total pseudocount.
+    pub fn with_multiplier(mut self, m: f32) -> Self {
+        self.m = m;
+        self
+    }
+}
+
+impl Prior<Protein> for SubstitutionPrior {
+    fn pseudocounts(&self, counts: &[f32]) -> Pseudocounts<Protein> {
+        let n = total::<Protein>(counts);
+        let mut pseudo = Pseudocounts::default();
+        if n <= 0.0 {
+            return pseudo;
+        }
+        let r = counts[..20].iter().filter(|&&x| x > 0.0).count() as f32;
+        let p = pseudo.as_mut();
+        for (b, &c) in counts[..20].iter().enumerate() {
+            if c > 0.0 {
+                for (a, &q) in self.conditional[b].iter().enumerate() {
+                    p[a] += self.m * r * (c / n) * q;
+                }
+            }
+        }
+        pseudo
+    }
+}
+
+// --- DirichletMixture --------------------------------------------------------
+
+/// The mixture coefficients of the 9-component mixture from Sjölander et al.
+const SJOLANDER_MIXTURE: [f64; 9] = [
+    0.178091, 0.056591, 0.0960191, 0.0781233, 0.0834977, 0.0904123, 0.114468, 0.0682132, 0.234585,
+];
+
+/// The Dirichlet parameters of the 9-component mixture from Sjölander et al.
+///
+/// Parameters are given in the `ACDEFGHIKLMNPQRSTVWY` order, which is the
+/// order of the symbols of the `Protein` alphabet.
+#[rustfmt::skip]
+const SJOLANDER_ALPHA: [[f64; 20]; 9] = [
+    [0.270671, 0.039848, 0.017576, 0.016415, 0.014268, 0.131916, 0.012391, 0.022599, 0.020358, 0.030727,
+     0.015315, 0.048298, 0.053803, 0.020662, 0.023612, 0.216147, 0.147226, 0.065438, 0.003758, 0.009621],
+    [0.021465, 0.010300, 0.011741, 0.010883, 0.385651, 0.016416, 0.076196, 0.035329, 0.013921, 0.093517,
+     0.022034, 0.028593, 0.013086, 0.023011, 0.018866, 0.029156, 0.018153, 0.036100, 0.071770, 0.419641],
+    [0.561459, 0.045448, 0.438366, 0.764167, 0.087364, 0.259114, 0.214940, 0.145928, 0.762204, 0.247320,
+     0.118662, 0.441564, 0.174822, 0.530840, 0.465529, 0.583402, 0.445586, 0.227050, 0.029510, 0.121090],
+    [0.070143, 0.011140, 0.019479, 0.094657, 0.013162, 0.048038, 0.077000, 0.032939, 0.576639, 0.072293,
+     0.028240, 0.080372, 0.037661, 0.185037, 0.506783, 0.073732, 0.071587, 0.042532, 0.011254, 0.028723],
+    [0.041103, 0.014794, 0.005610, 0.010216, 0.153602, 0.007797, 0.007175, 0.299635, 0.010849, 0.999446,
+     0.210189, 0.006127, 0.013021, 0.019798, 0.014509, 0.012049, 0.035799, 0.180085, 0.012744, 0.026466],
+    [0.115607, 0.037381, 0.012414, 0.018179, 0.051778, 0.017255, 0.004911, 0.796882, 0.017074, 0.285858,
+     0.075811, 0.014548, 0.015092, 0.011382, 0.012696, 0.027535, 0.088333, 0.944340, 0.004373, 0.016741],
+    [0.093461, 0.004737, 0.387252, 0.347841, 0.010822, 0.105877, 0.049776, 0.014963, 0.094276, 0.027761,
+     0.010040, 0.187869, 0.050018, 0.110039, 0.038668, 0.119471, 0.065802, 0.025430, 0.003215, 0.018742],
+    [0.452171, 0.114613, 0.062460, 0.115702, 0.284246, 0.140204, 0.100358, 0.550230, 0.143995, 0.700649,
+     0.276580, 0.118569, 0.097470, 0.126673, 0.143634, 0.278983, 0.358482, 0.661750, 0.061533, 0.199373],
+    [0.005193, 0.004039, 0.006722, 0.006121, 0.003468, 0.016931, 0.003647, 0.002184, 0.005019, 0.005990,
+     0.001473, 0.004158, 0.009055, 0.003630, 0.006583, 0.003172, 0.003690, 0.002967, 0.002772, 0.002686],
+];
+
+/// A Dirichlet mixture prior over the residues of a protein column.
+///
+/// The posterior probability of each component is computed from the
+/// observed counts of a column, and the pseudocounts are the Dirichlet
+/// parameters of the components weighted by their posterior probability.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirichletMixture {
+    /// The mixture coefficients of the components.
+    mixture: Vec<f64>,
+    /// The Dirichlet parameters of each component.
+    alpha: Vec<[f64; 20]>,
+}
+
+impl DirichletMixture {
+    /// Create a new Dirichlet mixture with the given components.
+    ///
+    /// Returns `None` if no component is given, or if any parameter is not
+    /// strictly positive.
+    pub fn new<I>(components: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (f64, [f64; 20])>,
+    {
+        let (mut mixture, alpha): (Vec<f64>, Vec<[f64; 20]>) = components.into_iter().unzip();
+        if mixture.is_empty()
+            || mixture.iter().any(|&q| q <= 0.0)
+            || alpha.iter().flatten().any(|&a| a <= 0.0)
+        {
+            return None;
+        }
+        let total: f64 = mixture.iter().sum();
+        mixture.iter_mut().for_each(|q| *q /= total);
+        Some(Self { mixture, alpha })
+    }
+
+    /// Create the 9-component mixture from Sjölander et al. (1996).
+    pub fn sjolander() -> Self {
+        Self::new(SJOLANDER_MIXTURE.into_iter().zip(SJOLANDER_ALPHA)).unwrap()
+    }
+
+    /// Compute the posterior probabilities of the components given counts.
+    fn posterior(&self, counts: &[f32]) -> Vec<f64> {
+        let n: f64 = counts[..20].iter().map(|&x| x as f64).sum();
+        let mut logp = self
+            .mixture
+            .iter()
+            .zip(self.alpha.iter())
+            .map(|(&q, alpha)| {
+                let a: f64 = alpha.iter().sum();
+                let mut l = q.ln() + ln_gamma(a) - ln_gamma(a + n);
+                for (&c, &x) in counts[..20].iter().zip(alpha.iter()) {
+                    l += ln_gamma(x + c as f64) - ln_gamma(x);
+                }
+                l
+            })
+            .collect::<Vec<f64>>();
+        let max = logp.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        logp.iter_mut().for_each(|l| *l = (*l - max).exp());
+        let total: f64 = logp.iter().sum();
+        logp.iter_mut().for_each(|l| *l /= total);
+        logp
+    }
+}
+
+impl Default for DirichletMixture {
+    fn default() -> Self {
+        Self::sjolander()
+    }
+}
+
+impl Prior<Protein> for DirichletMixture {
+    fn pseudocounts(&self, counts: &[f32]) -> Pseudocounts<Protein> {
+        let posterior = self.posterior(counts);
+        let mut pseudo = Pseudocounts::default();
+        let p = pseudo.as_mut();
+        for (&w, alpha) in posterior.iter().zip(self.alpha.iter()) {
+            for (x, &a) in p.iter_mut().zip(alpha.iter()) {
+                *x += (w * a) as f32;
+            }
+        }
+        pseudo
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::abc::AminoAcid;
+    use crate::abc::Dna;
+    use crate::abc::Nucleotide;
+    use crate::pwm::CountMatrix;
+    use crate::seq::EncodedSequence;
+
+    #[test]
+    fn test_background_prior() {
+        let bg = Background::<Dna>::new([0.4, 0.1, 0.4, 0.1, 0.0]).unwrap();
+        let prior = BackgroundPrior::new(2.0, bg);
+        let pseudo = prior.pseudocounts(&[1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(pseudo.counts(), &[0.8, 0.2, 0.8, 0.2, 0.0]);
+    }
+
+    #[test]
+    fn test_square_root_prior() {
+        let prior = SquareRootPrior::<Dna>::default();
+        let pseudo = prior.pseudocounts(&[9.0, 4.0, 2.0, 1.0, 3.0]);
+        assert_eq!(pseudo.counts(), &[1.0, 1.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_substitution_prior() {
+        let prior = SubstitutionPrior::blosum62();
+        let mut counts = [0.0; 21];
+        counts[AminoAcid::I.as_index()] = 10.0;
+        let pseudo = prior.pseudocounts(&counts);
+        let total: f32 = pseudo.counts().iter().sum();
+        assert!((total - 5.0).abs() < 1e-4);
+        // aliphatic residues are more likely than charged ones
+        assert!(
+            pseudo.counts()[AminoAcid::V.as_index()] > pseudo.counts()[AminoAcid::D.as_index()]
+        );
+        assert!(
+            pseudo.counts()[AminoAcid::L.as_index()] > pseudo.counts()[AminoAcid::K.as_index()]
+        );
+        assert_eq!(pseudo.counts()[AminoAcid::X.as_index()], 0.0);
+    }
+
+    #[test]
+    fn test_dirichlet_mixture() {
+        let prior = DirichletMixture::sjolander();
+        let mut counts = [0.0; 21];
+        counts[AminoAcid::L.as_index()] = 5.0;
+        counts[AminoAcid::I.as_index()] = 3.0;
+        let posterior = prior.posterior(&counts);
+        assert!((posterior.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        // the aliphatic component should explain the column best
+        let best = (0..posterior.len())
+            .max_by(|&i, &j| posterior[i].partial_cmp(&posterior[j]).unwrap())
+            .unwrap();
+        assert_eq!(best, 4);
+
+        let pseudo = prior.pseudocounts(&counts);
+        assert!(
+            pseudo.counts()[AminoAcid::M.as_index()] > pseudo.counts()[AminoAcid::D.as_index()]
+        );
+        assert_eq!(pseudo.counts()[AminoAcid::X.as_index()], 0.0);
+    }
+
+    #[test]
+    fn test_to_freq() {
+        let counts = CountMatrix::<Dna>::from_sequences(
+            ["AT", "AT", "AT", "AC"]
+                .iter()
+                .map(|s| EncodedSequence::encode(s).unwrap()),
+        )
+        .unwrap();
+        let freqs = counts.to_freq(SquareRootPrior::default());
+        assert_eq!(freqs[0][Nucleotide::A.as_index()], 4.5 / 6.0);
+        assert_eq!(freqs[0][Nucleotide::C.as_index()], 0.5 / 6.0);
+        assert_eq!(freqs[1][Nucleotide::T.as_index()], 3.5 / 6.0);
+        assert_eq!(counts.to_freq(0.5), freqs);
+    }
+}
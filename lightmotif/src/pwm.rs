@@ -1,16 +1,23 @@
 //! Storage types for the different stages of a PSSM construction.
 
+use std::fmt::Debug;
+use std::iter::Sum;
+use std::ops::AddAssign;
 use std::ops::Index;
+use std::ops::Range;
 
 use typenum::marker_traits::Unsigned;
 
 use super::abc::Alphabet;
 use super::abc::Background;
 use super::abc::ComplementableAlphabet;
-use super::abc::Pseudocounts;
+use super::abc::Dna;
+use super::abc::Nucleotide;
 use super::abc::Symbol;
 use super::dense::DenseMatrix;
 use super::err::InvalidData;
+use super::prior::Prior;
+use super::seq::AlignedSequence;
 use super::seq::EncodedSequence;
 
 macro_rules! matrix_traits {
@@ -35,23 +42,191 @@ macro_rules! matrix_traits {
     };
 }
 
+// --- Helpers -----------------------------------------------------------------
+
+/// Iterate over the symbols of a row, excluding the wildcard symbol.
+///
+/// Values are rescaled so that they sum to one over the known symbols,
+/// which makes metrics comparable whether or not the wildcard column of
+/// a matrix holds any data.
+pub(crate) fn known<A: Alphabet>(row: &[f32]) -> impl Iterator<Item = (A::Symbol, f32)> + '_ {
+    let wildcard = A::default_symbol().as_index();
+    let symbols = A::symbols()
+        .iter()
+        .filter(move |s| s.as_index() != wildcard);
+    let total: f32 = symbols.clone().map(|s| row[s.as_index()]).sum();
+    symbols.map(move |&s| {
+        let x = row[s.as_index()];
+        (s, if total > 0.0 { x / total } else { 0.0 })
+    })
+}
+
+/// Compute the Shannon entropy of a frequency row, in bits.
+fn row_entropy<A: Alphabet>(row: &[f32]) -> f32 {
+    known::<A>(row)
+        .filter(|&(_, p)| p > 0.0)
+        .map(|(_, p)| -p * p.log2())
+        .sum()
+}
+
+/// Compute the relative entropy of a frequency row against a background, in bits.
+pub(crate) fn row_relative_entropy<A: Alphabet>(row: &[f32], background: &[f32]) -> f32 {
+    known::<A>(row)
+        .zip(known::<A>(background))
+        .filter(|&((_, p), _)| p > 0.0)
+        .map(|((_, p), (_, b))| p * (p / b).log2())
+        .sum()
+}
+
+/// Find the known symbol with the highest value in a row.
+fn row_argmax<A: Alphabet>(row: &[f32]) -> A::Symbol {
+    A::symbols()
+        .iter()
+        .filter(|s| s.as_index() != A::default_symbol().as_index())
+        .fold(None, |best: Option<&A::Symbol>, s| match best {
+            Some(b) if row[b.as_index()] >= row[s.as_index()] => Some(b),
+            _ => Some(s),
+        })
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Find the known symbol with the lowest value in a row.
+fn row_argmin<A: Alphabet>(row: &[f32]) -> A::Symbol {
+    A::symbols()
+        .iter()
+        .filter(|s| s.as_index() != A::default_symbol().as_index())
+        .fold(None, |best: Option<&A::Symbol>, s| match best {
+            Some(b) if row[b.as_index()] <= row[s.as_index()] => Some(b),
+            _ => Some(s),
+        })
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Get the IUPAC code for a column using the rules from Cavener (1987).
+fn row_iupac(row: &[f32]) -> char {
+    let mut bases = known::<Dna>(row).collect::<Vec<_>>();
+    bases.sort_by(|x, y| y.1.total_cmp(&x.1));
+    let code = |symbols: &[Nucleotide]| {
+        let has = |n| symbols.contains(&n);
+        match (
+            has(Nucleotide::A),
+            has(Nucleotide::C),
+            has(Nucleotide::G),
+            has(Nucleotide::T),
+        ) {
+            (true, true, false, false) => 'M',
+            (true, false, true, false) => 'R',
+            (true, false, false, true) => 'W',
+            (false, true, true, false) => 'S',
+            (false, true, false, true) => 'Y',
+            (false, false, true, true) => 'K',
+            (true, true, true, false) => 'V',
+            (true, true, false, true) => 'H',
+            (true, false, true, true) => 'D',
+            (false, true, true, true) => 'B',
+            _ => 'N',
+        }
+    };
+    if bases[0].1 > 0.5 && bases[0].1 > 2.0 * bases[1].1 {
+        bases[0].0.as_char()
+    } else if bases[0].1 + bases[1].1 > 0.75 {
+        code(&[bases[0].0, bases[1].0])
+    } else if bases[3].1 == 0.0 {
+        code(&[bases[0].0, bases[1].0, bases[2].0])
+    } else {
+        'N'
+    }
+}
+
+/// Find the range of positions to keep when trimming a motif.
+///
+/// Returns an empty range starting at the end of the motif if no position
+/// reaches the threshold.
+fn trim_range(information: &[f32], threshold: f32) -> Range<usize> {
+    match information.iter().position(|&ic| ic >= threshold) {
+        None => information.len()..information.len(),
+        Some(start) => {
+            let end = information.iter().rposition(|&ic| ic >= threshold).unwrap();
+            start..end + 1
+        }
+    }
+}
+
+/// Extract a range of rows from a dense matrix.
+fn slice_rows<T: Default + Copy, K: Unsigned>(
+    data: &DenseMatrix<T, K>,
+    range: Range<usize>,
+) -> DenseMatrix<T, K> {
+    DenseMatrix::from_rows(data.iter().skip(range.start).take(range.len()))
+}
+
+// --- Count -----------------------------------------------------------------
+
+/// A numeric type that can be used to store symbol counts.
+///
+/// Counts are usually integers, but fractional counts are common in motif
+/// databases, or when sequences are weighted before being counted.
+pub trait Count: Copy + Default + PartialOrd + AddAssign + Sum + Debug + 'static {
+    /// The count of a single observation.
+    fn one() -> Self;
+    /// Convert the count to a single-precision float.
+    fn to_f32(self) -> f32;
+    /// Check whether the value is a valid count.
+    fn is_valid(self) -> bool;
+}
+
+impl Count for u32 {
+    #[inline]
+    fn one() -> Self {
+        1
+    }
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+    #[inline]
+    fn is_valid(self) -> bool {
+        true
+    }
+}
+
+impl Count for f32 {
+    #[inline]
+    fn one() -> Self {
+        1.0
+    }
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+    #[inline]
+    fn is_valid(self) -> bool {
+        self.is_finite() && self >= 0.0
+    }
+}
+
 // --- CountMatrix -------------------------------------------------------------
 
 /// A matrix storing symbol occurrences at each position.
+///
+/// Counts are stored as `u32` by default, use `CountMatrix<A, f32>` to
+/// store fractional counts.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct CountMatrix<A: Alphabet> {
+pub struct CountMatrix<A: Alphabet, T: Count = u32> {
     /// The alphabet of the count matrix.
     alphabet: std::marker::PhantomData<A>,
     /// The actual counts for each position of the motif.
-    data: DenseMatrix<u32, A::K>,
+    data: DenseMatrix<T, A::K>,
     /// The number of sequences from which this count matrix was obtained.
     #[allow(unused)]
-    n: u32,
+    n: T,
 }
 
-impl<A: Alphabet> CountMatrix<A> {
+impl<A: Alphabet, T: Count> CountMatrix<A, T> {
     /// Create a new count matrix without checking the contents.
-    fn new_unchecked(data: DenseMatrix<u32, A::K>, n: u32) -> Self {
+    fn new_unchecked(data: DenseMatrix<T, A::K>, n: T) -> Self {
         Self {
             alphabet: std::marker::PhantomData,
             n,
@@ -63,16 +238,77 @@ impl<A: Alphabet> CountMatrix<A> {
     ///
     /// The matrix must contain count data, for sequences of the same
     /// length, i.e. rows should all sum to the same value.
-    pub fn new(data: DenseMatrix<u32, A::K>) -> Result<Self, InvalidData> {
+    ///
+    /// # Errors
+    /// Returns an error if any of the counts is negative or not finite.
+    pub fn new(data: DenseMatrix<T, A::K>) -> Result<Self, InvalidData> {
         // Empty matrices contain valid data.
         if data.rows() == 0 {
-            return Ok(Self::new_unchecked(data, 0));
+            return Ok(Self::new_unchecked(data, T::default()));
+        }
+        // Check counts are valid.
+        if !data.iter().all(|row| row.iter().all(|x| x.is_valid())) {
+            return Err(InvalidData);
         }
         // Check row sums.
-        let n = data.iter().map(|row| row.iter().sum()).max().unwrap();
+        let n = data
+            .iter()
+            .map(|row| row.iter().copied().sum::<T>())
+            .fold(T::default(), |n, x| if x > n { x } else { n });
         Ok(Self::new_unchecked(data, n))
     }
 
+    /// Build a probability matrix from this count matrix using pseudo-counts.
+    ///
+    /// The pseudo-counts can be given as a constant, as a [`Pseudocounts`]
+    /// array, or computed for each column with any other [`Prior`].
+    pub fn to_freq<P>(&self, prior: P) -> FrequencyMatrix<A>
+    where
+        P: Prior<A>,
+    {
+        let mut probas = DenseMatrix::new(self.data.rows());
+        for i in 0..self.data.rows() {
+            let src = &self.data[i];
+            let dst = &mut probas[i];
+            for (j, &x) in src.iter().enumerate() {
+                dst[j] = x.to_f32();
+            }
+            let p = prior.pseudocounts(dst);
+            for (x, &c) in dst.iter_mut().zip(p.counts()) {
+                *x += c;
+            }
+            let s: f32 = dst.iter().sum();
+            for x in dst.iter_mut() {
+                *x /= s;
+            }
+        }
+        FrequencyMatrix::new_unchecked(probas)
+    }
+
+    /// The raw counts from the count matrix.
+    #[inline]
+    pub fn counts(&self) -> &DenseMatrix<T, A::K> {
+        &self.data
+    }
+
+    /// Remove uninformative positions from the edges of the motif.
+    ///
+    /// The information content of each position is computed from the
+    /// observed frequencies, without pseudocounts. See
+    /// [`FrequencyMatrix::trim`] for more details.
+    pub fn trim<B>(&self, threshold: f32, background: B) -> (Self, usize)
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        let ic = self.to_freq(0.0).information_content(background);
+        let range = trim_range(&ic, threshold);
+        let start = range.start;
+        let data = slice_rows(&self.data, range);
+        (Self::new_unchecked(data, self.n), start)
+    }
+}
+
+impl<A: Alphabet> CountMatrix<A> {
     /// Create a new count matrix from the given sequences.
     pub fn from_sequences<'seq, I>(sequences: I) -> Result<Self, InvalidData>
     where
@@ -104,35 +340,108 @@ impl<A: Alphabet> CountMatrix<A> {
         }
     }
 
-    /// Build a probability matrix from this count matrix using pseudo-counts.
-    pub fn to_freq<P>(&self, pseudo: P) -> FrequencyMatrix<A>
+    /// Create a new count matrix from the rows of a multiple alignment.
+    ///
+    /// Alignment columns where the fraction of gaps is strictly above
+    /// `max_gap_fraction` are skipped, so that a value of `0.0` keeps only
+    /// gap-free columns. Gaps in the columns that are kept do not contribute
+    /// any count. The count matrix is returned along with the indices of
+    /// the alignment columns that were kept, in order.
+    ///
+    /// # Errors
+    /// Returns an error if the aligned sequences do not all have the same
+    /// length.
+    pub fn from_alignment<I>(
+        sequences: I,
+        max_gap_fraction: f32,
+    ) -> Result<(Self, Vec<usize>), InvalidData>
     where
-        P: Into<Pseudocounts<A>>,
+        I: IntoIterator,
+        <I as IntoIterator>::Item: AsRef<AlignedSequence<A>>,
     {
-        let p = pseudo.into();
-        let mut probas = DenseMatrix::new(self.data.rows());
-        for i in 0..self.data.rows() {
-            let src = &self.data[i];
-            let dst = &mut probas[i];
-            for (j, &x) in src.iter().enumerate() {
-                dst[j] = x as f32 + p.counts()[j];
+        let mut n = 0;
+        let mut counts: Option<DenseMatrix<u32, A::K>> = None;
+        let mut gaps: Vec<u32> = Vec::new();
+        for seq in sequences {
+            let seq = seq.as_ref();
+            let d = match counts.as_mut() {
+                Some(d) => d,
+                None => {
+                    gaps = vec![0; seq.len()];
+                    counts = Some(DenseMatrix::new(seq.len()));
+                    counts.as_mut().unwrap()
+                }
+            };
+            if seq.len() != d.rows() {
+                return Err(InvalidData);
             }
-            let s: f32 = dst.iter().sum();
-            for x in dst.iter_mut() {
-                *x /= s;
+            for (i, x) in seq.iter().enumerate() {
+                match x {
+                    Some(s) => d[i][s.as_index()] += 1,
+                    None => gaps[i] += 1,
+                }
             }
+            n += 1;
         }
-        FrequencyMatrix::new_unchecked(probas)
+        let counts = match counts {
+            None => return Ok((Self::new_unchecked(DenseMatrix::new(0), 0), Vec::new())),
+            Some(counts) => counts,
+        };
+        let kept = gaps
+            .iter()
+            .enumerate()
+            .filter(|&(_, &g)| g as f32 <= max_gap_fraction * n as f32)
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        let data = DenseMatrix::from_rows(kept.iter().map(|&i| &counts[i]));
+        Ok((Self::new_unchecked(data, n), kept))
     }
+}
 
-    /// The raw counts from the count matrix.
-    #[inline]
-    pub fn counts(&self) -> &DenseMatrix<u32, A::K> {
-        &self.data
+impl<A: Alphabet> CountMatrix<A, f32> {
+    /// Create a new count matrix from the given weighted sequences.
+    ///
+    /// Each sequence contributes its weight to the counts of its symbols,
+    /// instead of a count of one. See the [`weighting`] module for some
+    /// methods to compute sequence weights.
+    ///
+    /// # Errors
+    /// Returns an error if the sequences do not all have the same length,
+    /// or if any of the weights is negative or not finite.
+    ///
+    /// [`weighting`]: crate::weighting
+    pub fn from_weighted_sequences<I, S>(sequences: I) -> Result<Self, InvalidData>
+    where
+        I: IntoIterator<Item = (S, f32)>,
+        S: AsRef<EncodedSequence<A>>,
+    {
+        let mut n = 0.0;
+        let mut data = None;
+        for (seq, weight) in sequences {
+            let seq = seq.as_ref();
+            let d = match data.as_mut() {
+                Some(d) => d,
+                None => {
+                    data = Some(DenseMatrix::new(seq.len()));
+                    data.as_mut().unwrap()
+                }
+            };
+            if seq.len() != d.rows() || !weight.is_valid() {
+                return Err(InvalidData);
+            }
+            for (i, x) in seq.into_iter().enumerate() {
+                d[i][x.as_index()] += weight;
+            }
+            n += weight;
+        }
+        match data {
+            None => Ok(Self::new_unchecked(DenseMatrix::new(0), n)),
+            Some(matrix) => Ok(Self::new_unchecked(matrix, n)),
+        }
     }
 }
 
-impl<A: ComplementableAlphabet> CountMatrix<A> {
+impl<A: ComplementableAlphabet, T: Count> CountMatrix<A, T> {
     /// Get the reverse-complement of this count matrix.
     pub fn reverse_complement(&self) -> Self {
         let mut data = DenseMatrix::new(self.data.rows());
@@ -145,6 +454,18 @@ impl<A: ComplementableAlphabet> CountMatrix<A> {
     }
 }
 
+impl<A: Alphabet> From<CountMatrix<A, u32>> for CountMatrix<A, f32> {
+    fn from(counts: CountMatrix<A, u32>) -> Self {
+        let mut data = DenseMatrix::new(counts.data.rows());
+        for (src, dst) in counts.data.iter().zip(data.iter_mut()) {
+            for (&x, y) in src.iter().zip(dst.iter_mut()) {
+                *y = x as f32;
+            }
+        }
+        Self::new_unchecked(data, counts.n as f32)
+    }
+}
+
 impl<A: Alphabet> FromIterator<EncodedSequence<A>> for Result<CountMatrix<A>, InvalidData> {
     fn from_iter<I>(iter: I) -> Self
     where
@@ -154,7 +475,25 @@ impl<A: Alphabet> FromIterator<EncodedSequence<A>> for Result<CountMatrix<A>, In
     }
 }
 
-matrix_traits!(CountMatrix, u32);
+impl<A: Alphabet, T: Count> AsRef<CountMatrix<A, T>> for CountMatrix<A, T> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<A: Alphabet, T: Count> AsRef<DenseMatrix<T, A::K>> for CountMatrix<A, T> {
+    fn as_ref(&self) -> &DenseMatrix<T, A::K> {
+        &self.data
+    }
+}
+
+impl<A: Alphabet, T: Count> Index<usize> for CountMatrix<A, T> {
+    type Output = <DenseMatrix<T, A::K> as Index<usize>>::Output;
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.data.index(index)
+    }
+}
 
 // --- FrequencyMatrix ---------------------------------------------------------
 
@@ -167,7 +506,7 @@ pub struct FrequencyMatrix<A: Alphabet> {
 
 impl<A: Alphabet> FrequencyMatrix<A> {
     /// Create a new frequency matrix without checking the contents.
-    fn new_unchecked(data: DenseMatrix<f32, A::K>) -> Self {
+    pub(crate) fn new_unchecked(data: DenseMatrix<f32, A::K>) -> Self {
         Self {
             alphabet: std::marker::PhantomData,
             data,
@@ -220,6 +559,120 @@ impl<A: Alphabet> FrequencyMatrix<A> {
         }
         ScoringMatrix::new(bg, scores)
     }
+
+    /// The Shannon entropy of each position of the motif, in bits.
+    pub fn entropy(&self) -> Vec<f32> {
+        self.data.iter().map(row_entropy::<A>).collect()
+    }
+
+    /// The information content of each position of the motif, in bits.
+    ///
+    /// The information content of a position is computed as the relative
+    /// entropy of its frequencies against the background frequencies. With
+    /// a uniform background, this is equal to `log2(K-1)` minus the Shannon
+    /// entropy of the position.
+    ///
+    /// # Note
+    /// The wildcard symbol is ignored in this method and in all other
+    /// metrics, and the remaining frequencies are rescaled to sum to one.
+    pub fn information_content<B>(&self, background: B) -> Vec<f32>
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        let bg = background.into().unwrap_or_default();
+        self.data
+            .iter()
+            .map(|row| row_relative_entropy::<A>(row, bg.frequencies()))
+            .collect()
+    }
+
+    /// The relative entropy of the motif against the background, in bits.
+    ///
+    /// This is the total information content of the motif, i.e. the sum
+    /// of the information content at each position.
+    pub fn relative_entropy<B>(&self, background: B) -> f32
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        self.information_content(background).iter().sum()
+    }
+
+    /// The consensus sequence of the motif, made of the most frequent symbols.
+    pub fn consensus(&self) -> String {
+        self.data
+            .iter()
+            .map(|row| row_argmax::<A>(row).as_char())
+            .collect()
+    }
+
+    /// The sequence with the highest odds ratio against the background.
+    pub fn best_sequence<B>(&self, background: B) -> EncodedSequence<A>
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        let weights = self.to_weight(background);
+        weights.best_sequence()
+    }
+
+    /// The sequence with the lowest odds ratio against the background.
+    pub fn worst_sequence<B>(&self, background: B) -> EncodedSequence<A>
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        let weights = self.to_weight(background);
+        weights.worst_sequence()
+    }
+
+    /// Remove uninformative positions from the edges of the motif.
+    ///
+    /// Leading and trailing positions with an information content (see
+    /// [`FrequencyMatrix::information_content`]) strictly below `threshold`
+    /// are removed, while positions inside the motif are always kept. The
+    /// trimmed matrix is returned along with the number of positions that
+    /// were removed from the start of the motif, which is the offset of the
+    /// trimmed motif inside the original one: a hit of the trimmed motif at
+    /// position `i` of a sequence corresponds to a hit of the original motif
+    /// at position `i - offset`.
+    ///
+    /// If no position reaches the threshold, an empty matrix is returned
+    /// with an offset equal to the length of the original motif.
+    pub fn trim<B>(&self, threshold: f32, background: B) -> (Self, usize)
+    where
+        B: Into<Option<Background<A>>>,
+    {
+        let ic = self.information_content(background);
+        let range = trim_range(&ic, threshold);
+        let start = range.start;
+        (Self::new_unchecked(slice_rows(&self.data, range)), start)
+    }
+}
+
+impl FrequencyMatrix<Dna> {
+    /// The average frequency of guanine and cytosine over the motif.
+    pub fn gc_content(&self) -> f32 {
+        if self.data.rows() == 0 {
+            return 0.0;
+        }
+        let gc: f32 = self
+            .data
+            .iter()
+            .flat_map(known::<Dna>)
+            .filter(|(s, _)| matches!(s, Nucleotide::G | Nucleotide::C))
+            .map(|(_, p)| p)
+            .sum();
+        gc / self.data.rows() as f32
+    }
+
+    /// The consensus sequence of the motif, using IUPAC ambiguity codes.
+    ///
+    /// Degenerate symbols are selected using the rules from Cavener (1987):
+    /// a single base is reported if its frequency is over 50% and more than
+    /// twice the second most frequent base; a two-base code is reported if
+    /// the two most frequent bases sum to over 75%; a three-base code is
+    /// reported if one of the bases never occurs; `N` is used otherwise.
+    pub fn iupac_consensus(&self) -> String {
+        self.data.iter().map(row_iupac).collect()
+    }
 }
 
 impl<A: ComplementableAlphabet> FrequencyMatrix<A> {
@@ -305,6 +758,62 @@ impl<A: Alphabet> WeightMatrix<A> {
         }
         ScoringMatrix::new(background, data)
     }
+
+    /// Get the frequencies corresponding to this weight matrix.
+    fn frequencies(&self) -> FrequencyMatrix<A> {
+        let mut data = self.data.clone();
+        for row in data.iter_mut() {
+            for (x, &f) in row.iter_mut().zip(self.background.frequencies()) {
+                *x *= f;
+            }
+        }
+        FrequencyMatrix::new_unchecked(data)
+    }
+
+    /// The Shannon entropy of each position of the motif, in bits.
+    pub fn entropy(&self) -> Vec<f32> {
+        self.frequencies().entropy()
+    }
+
+    /// The information content of each position of the motif, in bits.
+    ///
+    /// See [`FrequencyMatrix::information_content`] for more details.
+    pub fn information_content(&self) -> Vec<f32> {
+        self.frequencies()
+            .information_content(self.background.clone())
+    }
+
+    /// The relative entropy of the motif against the background, in bits.
+    pub fn relative_entropy(&self) -> f32 {
+        self.information_content().iter().sum()
+    }
+
+    /// The consensus sequence of the motif, made of the most frequent symbols.
+    pub fn consensus(&self) -> String {
+        self.frequencies().consensus()
+    }
+
+    /// The sequence with the highest odds ratio in this weight matrix.
+    pub fn best_sequence(&self) -> EncodedSequence<A> {
+        self.data.iter().map(row_argmax::<A>).collect()
+    }
+
+    /// The sequence with the lowest odds ratio in this weight matrix.
+    pub fn worst_sequence(&self) -> EncodedSequence<A> {
+        self.data.iter().map(row_argmin::<A>).collect()
+    }
+}
+
+impl WeightMatrix<Dna> {
+    /// The average frequency of guanine and cytosine over the motif.
+    pub fn gc_content(&self) -> f32 {
+        self.frequencies().gc_content()
+    }
+
+    /// The consensus sequence of the motif, using IUPAC ambiguity codes.
+    pub fn iupac_consensus(&self) -> String {
+        self.frequencies().iupac_consensus()
+    }
 }
 
 impl<A: ComplementableAlphabet> WeightMatrix<A> {
@@ -396,6 +905,82 @@ impl<A: Alphabet> ScoringMatrix<A> {
             .map(|row| row.iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap())
             .sum()
     }
+
+    /// Get the frequencies corresponding to this scoring matrix.
+    fn frequencies(&self) -> FrequencyMatrix<A> {
+        let mut data = self.data.clone();
+        for row in data.iter_mut() {
+            for (x, &f) in row.iter_mut().zip(self.background.frequencies()) {
+                *x = 2f32.powf(*x) * f;
+            }
+        }
+        FrequencyMatrix::new_unchecked(data)
+    }
+
+    /// The Shannon entropy of each position of the motif, in bits.
+    pub fn entropy(&self) -> Vec<f32> {
+        self.frequencies().entropy()
+    }
+
+    /// The information content of each position of the motif, in bits.
+    ///
+    /// See [`FrequencyMatrix::information_content`] for more details.
+    pub fn information_content(&self) -> Vec<f32> {
+        self.frequencies()
+            .information_content(self.background.clone())
+    }
+
+    /// The relative entropy of the motif against the background, in bits.
+    pub fn relative_entropy(&self) -> f32 {
+        self.information_content().iter().sum()
+    }
+
+    /// The consensus sequence of the motif, made of the most frequent symbols.
+    pub fn consensus(&self) -> String {
+        self.frequencies().consensus()
+    }
+
+    /// The sequence with the highest score in this scoring matrix.
+    ///
+    /// # Note
+    /// Unlike [`ScoringMatrix::max_score`], this method never selects the
+    /// wildcard symbol, so the score of the returned sequence may be lower
+    /// than the maximum score if the wildcard has the highest log-odds.
+    pub fn best_sequence(&self) -> EncodedSequence<A> {
+        self.data.iter().map(row_argmax::<A>).collect()
+    }
+
+    /// The sequence with the lowest score in this scoring matrix.
+    ///
+    /// # Note
+    /// Unlike [`ScoringMatrix::min_score`], this method never selects the
+    /// wildcard symbol, which usually has a score of `-f32::INFINITY`.
+    pub fn worst_sequence(&self) -> EncodedSequence<A> {
+        self.data.iter().map(row_argmin::<A>).collect()
+    }
+
+    /// Remove uninformative positions from the edges of the motif.
+    ///
+    /// See [`FrequencyMatrix::trim`] for more details.
+    pub fn trim(&self, threshold: f32) -> (Self, usize) {
+        let ic = self.information_content();
+        let range = trim_range(&ic, threshold);
+        let start = range.start;
+        let data = slice_rows(&self.data, range);
+        (Self::new(self.background.clone(), data), start)
+    }
+}
+
+impl ScoringMatrix<Dna> {
+    /// The average frequency of guanine and cytosine over the motif.
+    pub fn gc_content(&self) -> f32 {
+        self.frequencies().gc_content()
+    }
+
+    /// The consensus sequence of the motif, using IUPAC ambiguity codes.
+    pub fn iupac_consensus(&self) -> String {
+        self.frequencies().iupac_consensus()
+    }
 }
 
 impl<A: Alphabet> From<WeightMatrix<A>> for ScoringMatrix<A> {
@@ -405,3 +990,169 @@ impl<A: Alphabet> From<WeightMatrix<A>> for ScoringMatrix<A> {
 }
 
 matrix_traits!(ScoringMatrix, f32);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build_matrix() -> CountMatrix<Dna> {
+        #[rustfmt::skip]
+        let counts = DenseMatrix::from_rows([
+            //A  C  T  G  N
+            [10, 0, 0, 0, 0],
+            [ 5, 0, 0, 5, 0],
+            [ 4, 3, 3, 0, 0],
+            [ 3, 3, 2, 2, 0],
+            [ 0, 0, 9, 1, 0],
+        ]);
+        CountMatrix::new(counts).unwrap()
+    }
+
+    #[test]
+    fn test_information_content() {
+        let freq = build_matrix().to_freq(0.0);
+        let ic = freq.information_content(None);
+        assert_eq!(ic.len(), 5);
+        assert!((ic[0] - 2.0).abs() < 1e-5);
+        assert!((ic[1] - 1.0).abs() < 1e-5);
+        assert!(ic[3] < ic[2]);
+
+        let entropy = freq.entropy();
+        for (h, i) in entropy.iter().zip(ic.iter()) {
+            assert!((h + i - 2.0).abs() < 1e-5);
+        }
+
+        let pssm = freq.to_scoring(None);
+        for (x, y) in pssm.information_content().iter().zip(ic.iter()) {
+            assert!((x - y).abs() < 1e-5);
+        }
+        assert!((pssm.relative_entropy() - freq.relative_entropy(None)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_consensus() {
+        let freq = build_matrix().to_freq(0.0);
+        assert_eq!(freq.consensus(), "AAAAT");
+        assert_eq!(freq.iupac_consensus(), "ARHNT");
+        assert!((freq.gc_content() - 0.28).abs() < 1e-5);
+
+        let pssm = freq.to_scoring(None);
+        assert_eq!(pssm.consensus(), "AAAAT");
+        assert_eq!(pssm.iupac_consensus(), "ARHNT");
+        assert!((pssm.gc_content() - 0.28).abs() < 1e-5);
+
+        // an infinite value is normalized to a NaN frequency
+        let code = row_iupac(&[f32::INFINITY, 0.0, 0.0, 0.0, 0.0]);
+        assert!("ACGTMRWSYKVHDBN".contains(code), "{}", code);
+    }
+
+    #[test]
+    fn test_best_sequence() {
+        let pssm = build_matrix().to_freq(0.5).to_scoring(None);
+        let best = pssm.best_sequence();
+        let worst = pssm.worst_sequence();
+        assert_eq!(best.to_string(), "AAAAT");
+        assert_eq!(worst.to_string(), "CCGTA");
+
+        let score = |seq: &EncodedSequence<Dna>| -> f32 {
+            seq.iter()
+                .into_iter()
+                .enumerate()
+                .map(|(i, s)| pssm.weights()[i][s.as_index()])
+                .sum()
+        };
+        assert_eq!(score(&best), pssm.max_score());
+        assert!(score(&worst) > pssm.min_score());
+    }
+
+    #[test]
+    fn test_trim() {
+        #[rustfmt::skip]
+        let counts = DenseMatrix::from_rows([
+            //A  C  T  G  N
+            [ 3, 2, 3, 2, 0],
+            [10, 0, 0, 0, 0],
+            [ 3, 2, 3, 2, 0],
+            [ 0, 0, 0, 10, 0],
+            [ 2, 3, 2, 3, 0],
+            [ 3, 3, 2, 2, 0],
+        ]);
+        let counts = CountMatrix::<Dna>::new(counts).unwrap();
+
+        let (trimmed, offset) = counts.trim(0.5, None);
+        assert_eq!(offset, 1);
+        assert_eq!(trimmed.counts().rows(), 3);
+        assert_eq!(&trimmed.counts()[0], &counts.counts()[1]);
+        assert_eq!(&trimmed.counts()[2], &counts.counts()[3]);
+
+        let freq = counts.to_freq(0.1);
+        let (trimmed, offset) = freq.trim(0.5, None);
+        assert_eq!(offset, 1);
+        assert_eq!(&trimmed[0], &freq[1]);
+
+        let pssm = freq.to_scoring(None);
+        let (trimmed, offset) = pssm.trim(0.5);
+        assert_eq!(offset, 1);
+        assert_eq!(trimmed.len(), 3);
+        assert_eq!(&trimmed[2], &pssm[3]);
+
+        let (trimmed, offset) = pssm.trim(3.0);
+        assert_eq!(offset, 6);
+        assert_eq!(trimmed.len(), 0);
+    }
+
+    #[test]
+    fn test_fractional_counts() {
+        let mut data = DenseMatrix::<f32, <Dna as Alphabet>::K>::new(2);
+        data[0][Nucleotide::A.as_index()] = 0.5;
+        data[0][Nucleotide::T.as_index()] = 1.5;
+        data[1][Nucleotide::G.as_index()] = 2.0;
+        let counts = CountMatrix::<Dna, f32>::new(data).unwrap();
+
+        let freqs = counts.to_freq(0.0);
+        assert_eq!(freqs[0][Nucleotide::A.as_index()], 0.25);
+        assert_eq!(freqs[0][Nucleotide::T.as_index()], 0.75);
+        assert_eq!(freqs[1][Nucleotide::G.as_index()], 1.0);
+
+        let rc = counts.reverse_complement();
+        assert_eq!(rc[0][Nucleotide::C.as_index()], 2.0);
+        assert_eq!(rc[1][Nucleotide::A.as_index()], 1.5);
+        assert_eq!(rc[1][Nucleotide::T.as_index()], 0.5);
+
+        let integer = build_matrix();
+        let fractional = CountMatrix::<Dna, f32>::from(integer.clone());
+        assert_eq!(fractional.to_freq(0.1), integer.to_freq(0.1));
+
+        let mut data = DenseMatrix::<f32, <Dna as Alphabet>::K>::new(1);
+        data[0][Nucleotide::A.as_index()] = -1.0;
+        assert!(CountMatrix::<Dna, f32>::new(data.clone()).is_err());
+        data[0][Nucleotide::A.as_index()] = f32::NAN;
+        assert!(CountMatrix::<Dna, f32>::new(data.clone()).is_err());
+        data[0][Nucleotide::A.as_index()] = f32::INFINITY;
+        assert!(CountMatrix::<Dna, f32>::new(data).is_err());
+    }
+
+    #[test]
+    fn test_from_alignment() {
+        let sequences = ["AT-GC", "AT-GG", "A--GC", "ATTGC"]
+            .iter()
+            .map(|s| AlignedSequence::<Dna>::encode(s).unwrap())
+            .collect::<Vec<_>>();
+
+        let (counts, kept) = CountMatrix::from_alignment(&sequences, 0.0).unwrap();
+        assert_eq!(kept, vec![0, 3, 4]);
+        assert_eq!(counts.counts().rows(), 3);
+        assert_eq!(counts[0][Nucleotide::A.as_index()], 4);
+        assert_eq!(counts[2][Nucleotide::C.as_index()], 3);
+
+        let (counts, kept) = CountMatrix::from_alignment(&sequences, 0.5).unwrap();
+        assert_eq!(kept, vec![0, 1, 3, 4]);
+        assert_eq!(counts[1][Nucleotide::T.as_index()], 3);
+
+        let (_, kept) = CountMatrix::from_alignment(&sequences, 1.0).unwrap();
+        assert_eq!(kept, vec![0, 1, 2, 3, 4]);
+
+        let ragged = [sequences[0].clone(), AlignedSequence::encode("AT").unwrap()];
+        assert!(CountMatrix::from_alignment(&ragged, 0.0).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! Random sampling of synthetic sequences and motif sites.
+//!
+//! Samplers take any random number generator implementing [`Rng`], so a
+//! seeded generator (such as `StdRng::seed_from_u64`) can be used to get
+//! reproducible data sets. Sites are sampled from a [`FrequencyMatrix`]
+//! through its [`Distribution`] implementation, background sequences are
+//! sampled from a [`Background`] or from a [`MarkovModel`], and sites can
+//! be planted in background sequences with [`plant`]. Sequences can also
+//! be shuffled while preserving their composition with [`shuffle`].
+
+use std::collections::HashMap;
+
+use rand::distributions::Distribution;
+use rand::Rng;
+
+use super::abc::Alphabet;
+use super::abc::Background;
+use super::abc::Symbol;
+use super::dense::DenseMatrix;
+use super::num::Unsigned;
+use super::pwm::FrequencyMatrix;
+use super::seq::EncodedSequence;
+
+/// Draw a symbol index from a discrete distribution.
+///
+/// The weights do not need to be normalized. The last symbol with a
+/// non-zero weight is returned in case of rounding errors.
+fn draw<R: Rng + ?Sized>(rng: &mut R, weights: &[f32]) -> usize {
+    let total: f32 = weights.iter().sum();
+    let mut x = rng.gen::<f32>() * total;
+    let mut last = 0;
+    for (i, &w) in weights.iter().enumerate() {
+        if w > 0.0 {
+            if x < w {
+                return i;
+            }
+            x -= w;
+            last = i;
+        }
+    }
+    last
+}
+
+/// Get the symbols of an alphabet, excluding the wildcard symbol.
+fn known<A: Alphabet>() -> Vec<A::Symbol> {
+    let wildcard = A::default_symbol().as_index();
+    A::symbols()
+        .iter()
+        .filter(|s| s.as_index() != wildcard)
+        .cloned()
+        .collect()
+}
+
+// --- SequenceSampler ---------------------------------------------------------
+
+/// A model that can generate random sequences of arbitrary length.
+pub trait SequenceSampler<A: Alphabet> {
+    /// Sample a random sequence of the given length.
+    fn sample_sequence<R: Rng + ?Sized>(&self, rng: &mut R, length: usize) -> EncodedSequence<A>;
+}
+
+impl<A: Alphabet> SequenceSampler<A> for Background<A> {
+    fn sample_sequence<R: Rng + ?Sized>(&self, rng: &mut R, length: usize) -> EncodedSequence<A> {
+        let symbols = A::symbols();
+        (0..length)
+            .map(|_| symbols[draw(rng, self.frequencies())])
+            .collect()
+    }
+}
+
+// --- FrequencyMatrix ---------------------------------------------------------
+
+impl<A: Alphabet> Distribution<EncodedSequence<A>> for FrequencyMatrix<A> {
+    /// Sample a site, drawing each position independently.
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> EncodedSequence<A> {
+        let symbols = A::symbols();
+        let data: &DenseMatrix<f32, A::K> = self.as_ref();
+        data.iter().map(|row| symbols[draw(rng, row)]).collect()
+    }
+}
+
+// --- MarkovModel -------------------------------------------------------------
+
+/// A background model where each symbol depends on the preceding symbols.
+///
+/// A Markov model of order `k` stores the probability of each symbol given
+/// the `k` preceding symbols. The first `k` symbols of a sampled sequence,
+/// which do not have a complete context, are drawn from the zero-order
+/// frequencies of the model. The wildcard symbol is never sampled.
+#[derive(Clone, Debug)]
+pub struct MarkovModel<A: Alphabet> {
+    /// The order of the model.
+    order: usize,
+    /// The non-wildcard symbols, in the order used to encode contexts.
+    symbols: Vec<A::Symbol>,
+    /// The zero-order frequencies of the symbols.
+    initial: Vec<f32>,
+    /// The transition probabilities, indexed by context.
+    transitions: Vec<Vec<f32>>,
+}
+
+impl<A: Alphabet> MarkovModel<A> {
+    /// Train a Markov model of the given order from a set of sequences.
+    ///
+    /// The `pseudocount` is added to every transition count, so that
+    /// contexts that were not observed in the training sequences produce
+    /// uniformly distributed symbols. Contexts containing the wildcard
+    /// symbol are ignored.
+    pub fn train<I>(order: usize, sequences: I, pseudocount: f32) -> Self
+    where
+        I: IntoIterator,
+        <I as IntoIterator>::Item: AsRef<EncodedSequence<A>>,
+    {
+        let symbols = known::<A>();
+        let k = symbols.len();
+        let mut rank = vec![None; A::K::USIZE];
+        for (i, s) in symbols.iter().enumerate() {
+            rank[s.as_index()] = Some(i);
+        }
+
+        let mut initial = vec![pseudocount; k];
+        let mut transitions = vec![vec![pseudocount; k]; k.pow(order as u32)];
+        for seq in sequences {
+            let ranks = seq
+                .as_ref()
+                .into_iter()
+                .map(|s| rank[s.as_index()])
+                .collect::<Vec<_>>();
+            for (i, r) in ranks.iter().enumerate() {
+                let r = match r {
+                    Some(r) => *r,
+                    None => continue,
+                };
+                initial[r] += 1.0;
+                if i >= order {
+                    let context = ranks[i - order..i]
+                        .iter()
+                        .try_fold(0, |ctx, &x| x.map(|x| ctx * k + x));
+                    if let Some(ctx) = context {
+                        transitions[ctx][r] += 1.0;
+                    }
+                }
+            }
+        }
+
+        Self {
+            order,
+            symbols,
+            initial,
+            transitions,
+        }
+    }
+
+    /// The order of the Markov model.
+    #[inline]
+    pub fn order(&self) -> usize {
+        self.order
+    }
+}
+
+impl<A: Alphabet> SequenceSampler<A> for MarkovModel<A> {
+    fn sample_sequence<R: Rng + ?Sized>(&self, rng: &mut R, length: usize) -> EncodedSequence<A> {
+        let k = self.symbols.len();
+        let modulo = k.pow(self.order as u32);
+        let mut context = 0;
+        let mut data = Vec::with_capacity(length);
+        for i in 0..length {
+            let r = match i < self.order {
+                true => draw(rng, &self.initial),
+                false => draw(rng, &self.transitions[context]),
+            };
+            if modulo > 1 {
+                context = (context * k + r) % modulo;
+            }
+            data.push(self.symbols[r]);
+        }
+        EncodedSequence::new(data)
+    }
+}
+
+// --- Planting ----------------------------------------------------------------
+
+/// The ground truth for a site planted in a sequence.
+#[derive(Clone, Debug)]
+pub struct PlantedSite<A: Alphabet> {
+    /// The index of the sequence where the site was planted.
+    pub sequence: usize,
+    /// The position of the first symbol of the site in the sequence.
+    pub position: usize,
+    /// The planted site.
+    pub site: EncodedSequence<A>,
+}
+
+/// Plant sites sampled from a motif into background sequences.
+///
+/// Each sequence receives a site with the given `probability`, at a
+/// position drawn uniformly among the positions where the site fits
+/// entirely; sequences shorter than the motif never receive a site.
+/// The planted sites replace the background symbols, so the length of
+/// the sequences is unchanged. Returns the planted sites, in sequence
+/// order.
+pub fn plant<A, R>(
+    rng: &mut R,
+    sequences: &mut [EncodedSequence<A>],
+    motif: &FrequencyMatrix<A>,
+    probability: f32,
+) -> Vec<PlantedSite<A>>
+where
+    A: Alphabet,
+    R: Rng + ?Sized,
+{
+    let width = AsRef::<DenseMatrix<f32, A::K>>::as_ref(motif).rows();
+    let mut planted = Vec::new();
+    for (i, seq) in sequences.iter_mut().enumerate() {
+        if seq.len() < width || rng.gen::<f32>() >= probability {
+            continue;
+        }
+        let position = rng.gen_range(0..=seq.len() - width);
+        let site = motif.sample(rng);
+        *seq = seq[..position]
+            .iter()
+            .chain(&site)
+            .chain(&seq[position + width..])
+            .cloned()
+            .collect();
+        planted.push(PlantedSite {
+            sequence: i,
+            position,
+            site,
+        });
+    }
+    planted
+}
+
+// --- Shuffling ---------------------------------------------------------------
+
+/// Shuffle a segment while preserving its k-let counts.
+///
+/// Implements the Euler path algorithm from Altschul & Erickson (1985), as
+/// described for uShuffle (Jiang et al., 2008): the segment is viewed as a
+/// path in the multigraph whose vertices are the (k-1)-lets and whose edges
+/// are the k-lets of the segment, and a random Eulerian path with the same
+/// start and end vertices is drawn using a random arborescence built with
+/// Wilson's algorithm.
+fn shuffle_klets<S: Symbol, R: Rng + ?Sized>(rng: &mut R, segment: &[S], k: usize) -> Vec<S> {
+    if segment.len() <= k {
+        return segment.to_vec();
+    }
+
+    // build the vertices in order of first occurrence for determinism
+    let mut ids = HashMap::new();
+    let mut vertices = Vec::new();
+    let mut path = Vec::with_capacity(segment.len() - k + 2);
+    for window in segment.windows(k - 1) {
+        let key = window.iter().map(|s| s.as_index()).collect::<Vec<_>>();
+        let id = *ids.entry(key).or_insert_with(|| {
+            vertices.push(window[k - 2]);
+            vertices.len() - 1
+        });
+        path.push(id);
+    }
+
+    // build the edge lists of each vertex
+    let mut edges = vec![Vec::new(); vertices.len()];
+    for w in path.windows(2) {
+        edges[w[0]].push(w[1]);
+    }
+    let start = path[0];
+    let end = *path.last().unwrap();
+
+    // build a random arborescence rooted at the last vertex
+    let mut in_tree = vec![false; vertices.len()];
+    let mut next = vec![0; vertices.len()];
+    in_tree[end] = true;
+    for u in 0..vertices.len() {
+        let mut v = u;
+        while !in_tree[v] {
+            next[v] = rng.gen_range(0..edges[v].len());
+            v = edges[v][next[v]];
+        }
+        let mut v = u;
+        while !in_tree[v] {
+            in_tree[v] = true;
+            v = edges[v][next[v]];
+        }
+    }
+
+    // shuffle the edges of each vertex, keeping tree edges last
+    for (v, e) in edges.iter_mut().enumerate() {
+        let last = match v == end {
+            true => None,
+            false => Some(e.swap_remove(next[v])),
+        };
+        for i in (1..e.len()).rev() {
+            e.swap(i, rng.gen_range(0..=i));
+        }
+        e.extend(last);
+    }
+
+    // follow the Eulerian path
+    let mut shuffled = segment[..k - 1].to_vec();
+    let mut used = vec![0; vertices.len()];
+    let mut v = start;
+    for _ in 1..path.len() {
+        let w = edges[v][used[v]];
+        used[v] += 1;
+        shuffled.push(vertices[w]);
+        v = w;
+    }
+    shuffled
+}
+
+/// Shuffle a sequence while preserving its k-let composition.
+///
+/// With `k = 1`, the symbols of the sequence are permuted uniformly. With
+/// `k > 1`, the shuffled sequence has the same counts of every k-let as
+/// the original sequence, and starts and ends with the same (k-1)-lets.
+/// In both cases, the wildcard symbols are kept at their positions: for
+/// `k > 1`, each run of symbols between wildcards is shuffled separately.
+///
+/// # Panics
+/// Panics if `k` is zero.
+pub fn shuffle<A, R>(rng: &mut R, sequence: &EncodedSequence<A>, k: usize) -> EncodedSequence<A>
+where
+    A: Alphabet,
+    R: Rng + ?Sized,
+{
+    assert!(k > 0, "cannot shuffle with k = 0");
+    let wildcard = A::default_symbol().as_index();
+    let mut data = sequence[..].to_vec();
+    if k == 1 {
+        let positions = (0..data.len())
+            .filter(|&i| data[i].as_index() != wildcard)
+            .collect::<Vec<usize>>();
+        for i in (1..positions.len()).rev() {
+            data.swap(positions[i], positions[rng.gen_range(0..=i)]);
+        }
+    } else {
+        let mut start = 0;
+        while start < data.len() {
+            let length = data[start..]
+                .iter()
+                .position(|s| s.as_index() == wildcard)
+                .unwrap_or(data.len() - start);
+            let shuffled = shuffle_klets(rng, &data[start..start + length], k);
+            data[start..start + length].copy_from_slice(&shuffled);
+            start += length + 1;
+        }
+    }
+    EncodedSequence::new(data)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::abc::Nucleotide;
+    use crate::pwm::CountMatrix;
+
+    fn build_motif() -> FrequencyMatrix<Dna> {
+        CountMatrix::<Dna>::from_sequences(
+            ["GATTACA", "GATTACA", "GATTACA"]
+                .iter()
+                .map(|s| EncodedSequence::encode(s).unwrap()),
+        )
+        .unwrap()
+        .to_freq(0.0)
+    }
+
+    #[test]
+    fn test_sample_site() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let site: EncodedSequence<Dna> = rng.sample(build_motif());
+        assert_eq!(site.to_string(), "GATTACA");
+    }
+
+    #[test]
+    fn test_sample_background() {
+        let bg = Background::<Dna>::new([0.5, 0.0, 0.5, 0.0, 0.0]).unwrap();
+        let seq1 = bg.sample_sequence(&mut StdRng::seed_from_u64(1), 100);
+        let seq2 = bg.sample_sequence(&mut StdRng::seed_from_u64(1), 100);
+        assert_eq!(seq1.len(), 100);
+        assert_eq!(seq1.to_string(), seq2.to_string());
+        assert!(seq1
+            .into_iter()
+            .all(|&n| n == Nucleotide::A || n == Nucleotide::T));
+    }
+
+    #[test]
+    fn test_sample_markov() {
+        let training = [EncodedSequence::<Dna>::encode("ACGTACGTACGTNACGT").unwrap()];
+        let model = MarkovModel::train(1, &training, 0.0);
+        assert_eq!(model.order(), 1);
+        let seq = model.sample_sequence(&mut StdRng::seed_from_u64(7), 50);
+        let text = seq.to_string();
+        assert_eq!(text.len(), 50);
+        // every transition must have been observed in training
+        let (first, rest) = text.split_at(1);
+        let offset = "ACGT".find(first).unwrap();
+        assert!(rest
+            .chars()
+            .zip("ACGT".chars().cycle().skip(offset + 1))
+            .all(|(x, y)| x == y));
+    }
+
+    fn klets(sequence: &str, k: usize) -> Vec<&str> {
+        let mut klets = (0..=sequence.len() - k)
+            .map(|i| &sequence[i..i + k])
+            .collect::<Vec<_>>();
+        klets.sort();
+        klets
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let seq = EncodedSequence::<Dna>::encode("ATGCGATTACAGGCTTANNACGTTAGCATCGATG").unwrap();
+        let text = seq.to_string();
+        for k in 1..=3 {
+            let shuffled = shuffle(&mut StdRng::seed_from_u64(42), &seq, k);
+            let other = shuffle(&mut StdRng::seed_from_u64(42), &seq, k);
+            assert_eq!(shuffled.to_string(), other.to_string());
+            let shuffled = shuffled.to_string();
+            assert_eq!(&shuffled[17..19], "NN");
+            assert_eq!(klets(&shuffled, 1), klets(&text, 1));
+            for (x, y) in [(0, 17), (19, text.len())].iter().filter(|_| k > 1) {
+                assert_eq!(klets(&shuffled[*x..*y], k), klets(&text[*x..*y], k));
+            }
+        }
+        let shuffled = shuffle(&mut StdRng::seed_from_u64(42), &seq, 2).to_string();
+        assert_ne!(shuffled, text);
+    }
+
+    #[test]
+    fn test_plant() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let bg = Background::<Dna>::new([0.0, 0.5, 0.0, 0.5, 0.0]).unwrap();
+        let mut sequences = (0..20)
+            .map(|_| bg.sample_sequence(&mut rng, 30))
+            .collect::<Vec<_>>();
+        let planted = plant(&mut rng, &mut sequences, &build_motif(), 0.5);
+        assert!(!planted.is_empty());
+        assert!(planted.len() < 20);
+        for site in planted.iter() {
+            let seq = &sequences[site.sequence];
+            assert_eq!(seq.len(), 30);
+            let text = seq.to_string();
+            assert_eq!(&text[site.position..site.position + 7], "GATTACA");
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! Alternative scanning algorithms for high score thresholds.
+//!
+//! The [`Pipeline`](crate::pli::Pipeline) scores every position of a
+//! sequence, which is optimal when all scores are needed, but wasteful when
+//! only the few positions above a high threshold are of interest. The
+//! scanners of this module avoid computing most scores, and report the same
+//! positions as [`Threshold::threshold`](crate::pli::Threshold::threshold).
+
+use typenum::marker_traits::Unsigned;
+
+use super::abc::Alphabet;
+use super::abc::Background;
+use super::abc::Symbol;
+use super::dense::DenseMatrix;
+use super::pwm::ScoringMatrix;
+use super::seq::EncodedSequence;
+use super::stats::ScoreDistribution;
+use super::util::bounds;
+use super::util::tolerance;
+
+// --- Lookahead ---------------------------------------------------------------
+
+/// A lookahead filtering scanner, after MOODS (Korhonen et al., 2009).
+///
+/// Columns of the scoring matrix are visited by decreasing information
+/// content, and the window is abandoned as soon as the partial score plus
+/// the maximum score of the remaining columns falls below the threshold.
+/// For high thresholds, most windows are abandoned after a few columns.
+#[derive(Clone, Debug)]
+pub struct Lookahead<A: Alphabet> {
+    pssm: ScoringMatrix<A>,
+    /// The columns of the matrix, by decreasing information content.
+    order: Vec<usize>,
+    /// The maximum score of the columns after each column of `order`.
+    bounds: Vec<f32>,
+    /// The tolerance on the bounds for rounding errors.
+    tolerance: f32,
+}
+
+impl<A: Alphabet> Lookahead<A> {
+    /// Create a new lookahead scanner for the given scoring matrix.
+    pub fn new(pssm: &ScoringMatrix<A>) -> Self {
+        let information = pssm.information_content();
+        let mut order = (0..pssm.len()).collect::<Vec<usize>>();
+        order.sort_by(|&i, &j| {
+            information[j]
+                .partial_cmp(&information[i])
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(i.cmp(&j))
+        });
+
+        let bounds = bounds(pssm, &order);
+        let tolerance = tolerance(pssm);
+
+        Self {
+            pssm: pssm.clone(),
+            order,
+            bounds,
+            tolerance,
+        }
+    }
+
+    /// The scoring matrix of the scanner.
+    pub fn pssm(&self) -> &ScoringMatrix<A> {
+        &self.pssm
+    }
+
+    /// Compute the exact score of the window starting at position `i`.
+    ///
+    /// Columns are summed in order, so that scores are identical to the
+    /// scores computed by the pipelines.
+    fn score(&self, sequence: &EncodedSequence<A>, i: usize) -> f32 {
+        let weights = self.pssm.weights();
+        let mut score = 0.0;
+        for j in 0..self.pssm.len() {
+            score += weights[j][sequence[i + j].as_index()];
+        }
+        score
+    }
+
+    /// Return the positions with a score equal to or greater than the threshold.
+    ///
+    /// Positions are returned in increasing order.
+    pub fn threshold(&self, sequence: &EncodedSequence<A>, threshold: f32) -> Vec<usize> {
+        let mut positions = Vec::new();
+        if self.pssm.len() > sequence.len() {
+            return positions;
+        }
+        let weights = self.pssm.weights();
+        let cutoff = threshold - self.tolerance;
+        'window: for i in 0..sequence.len() - self.pssm.len() + 1 {
+            let mut partial = 0.0;
+            for (k, &j) in self.order.iter().enumerate() {
+                partial += weights[j][sequence[i + j].as_index()];
+                if partial + self.bounds[k + 1] < cutoff {
+                    continue 'window;
+                }
+            }
+            if self.score(sequence, i) >= threshold {
+                positions.push(i);
+            }
+        }
+        positions
+    }
+}
+
+// --- MultiScanner ------------------------------------------------------------
+
+/// A node of the automaton without a transition or a dictionary link.
+const NONE: u32 = u32::MAX;
+
+/// An Aho-Corasick automaton over words of symbol indices.
+#[derive(Clone, Debug)]
+struct Automaton {
+    /// The number of symbols of the alphabet.
+    symbols: usize,
+    /// The transitions of each node, for every symbol.
+    transitions: Vec<u32>,
+    /// The motif and window offset of the words ending at each node.
+    outputs: Vec<Vec<(u32, u32)>>,
+    /// The closest node with outputs among the suffixes of each node.
+    links: Vec<u32>,
+}
+
+impl Automaton {
+    /// Create an automaton with only a root node.
+    fn new(symbols: usize) -> Self {
+        Self {
+            symbols,
+            transitions: vec![NONE; symbols],
+            outputs: vec![Vec::new()],
+            links: vec![NONE],
+        }
+    }
+
+    /// Add a word to the trie of the automaton.
+    fn insert(&mut self, word: &[usize], output: (u32, u32)) {
+        let mut node = 0;
+        for &x in word {
+            let next = self.transitions[node * self.symbols + x];
+            node = if next == NONE {
+                let n = self.outputs.len();
+                self.transitions[node * self.symbols + x] = n as u32;
+                self.transitions
+                    .resize(self.transitions.len() + self.symbols, NONE);
+                self.outputs.push(Vec::new());
+                self.links.push(NONE);
+                n
+            } else {
+                next as usize
+            };
+        }
+        self.outputs[node].push(output);
+    }
+
+    /// Compute the failure transitions and dictionary links of the trie.
+    fn build(&mut self) {
+        let k = self.symbols;
+        let mut failures = vec![0u32; self.outputs.len()];
+        let mut queue = std::collections::VecDeque::new();
+        for x in 0..k {
+            match self.transitions[x] {
+                NONE => self.transitions[x] = 0,
+                child => queue.push_back(child as usize),
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            let failure = failures[node] as usize;
+            self.links[node] = if self.outputs[failure].is_empty() {
+                self.links[failure]
+            } else {
+                failure as u32
+            };
+            for x in 0..k {
+                let child = self.transitions[node * k + x];
+                let target = self.transitions[failure * k + x];
+                if child == NONE {
+                    self.transitions[node * k + x] = target;
+                } else {
+                    failures[child as usize] = target;
+                    queue.push_back(child as usize);
+                }
+            }
+        }
+    }
+}
+
+/// A scanner for many scoring matrices at once, with an Aho-Corasick automaton.
+///
+/// For each matrix, a window of consecutive columns is selected, and all the
+/// words that could be part of a site above the threshold are enumerated,
+/// using the score distribution of the window to choose the longest window
+/// with a reasonable number of words. The words of all matrices are then
+/// matched with a single automaton, and candidate sites are verified with
+/// exact scoring. Motifs with low thresholds, for which no window is
+/// selective, are still supported, but yield many candidates.
+#[derive(Clone, Debug)]
+pub struct MultiScanner<A: Alphabet> {
+    scanners: Vec<Lookahead<A>>,
+    thresholds: Vec<f32>,
+    /// The length of the window of each matrix.
+    windows: Vec<usize>,
+    automaton: Automaton,
+}
+
+impl<A: Alphabet> MultiScanner<A> {
+    /// The maximum length of the windows.
+    const MAX_WINDOW: usize = 10;
+    /// The number of words above which a window is not selective enough.
+    const MAX_WORDS: f64 = 4096.0;
+    /// The granularity of the score distributions used to choose windows.
+    const GRANULARITY: f64 = 0.25;
+
+    /// Create a new scanner for the given matrices and score thresholds.
+    ///
+    /// # Panics
+    /// Panics if the number of thresholds differs from the number of matrices.
+    pub fn new(matrices: &[ScoringMatrix<A>], thresholds: &[f32]) -> Self {
+        assert_eq!(
+            matrices.len(),
+            thresholds.len(),
+            "expected one threshold per matrix"
+        );
+        let mut automaton = Automaton::new(A::K::USIZE);
+        let mut scanners = Vec::with_capacity(matrices.len());
+        let mut windows = Vec::with_capacity(matrices.len());
+        for (motif, (pssm, &threshold)) in matrices.iter().zip(thresholds).enumerate() {
+            let scanner = Lookahead::new(pssm);
+            let cutoff = threshold - scanner.tolerance;
+            let (start, length) = Self::window(pssm, cutoff);
+            // empty matrices have no window, and are scored without the automaton
+            if length > 0 {
+                let output = (motif as u32, start as u32);
+                Self::enumerate(pssm, start, length, cutoff, |word| {
+                    automaton.insert(word, output)
+                });
+            }
+            scanners.push(scanner);
+            windows.push(length);
+        }
+        automaton.build();
+        Self {
+            scanners,
+            thresholds: thresholds.to_vec(),
+            windows,
+            automaton,
+        }
+    }
+
+    /// Select the window of columns used to index the given matrix.
+    fn window(pssm: &ScoringMatrix<A>, cutoff: f32) -> (usize, usize) {
+        let weights = pssm.weights();
+        let maxima = weights
+            .iter()
+            .map(|row| row.iter().cloned().fold(f32::NEG_INFINITY, f32::max))
+            .collect::<Vec<f32>>();
+        let total: f32 = maxima.iter().sum();
+        let information = pssm.information_content();
+        let symbols = Background::<A>::uniform()
+            .frequencies()
+            .iter()
+            .filter(|&&f| f > 0.0)
+            .count() as f64;
+
+        let mut best = (0, pssm.len().min(1));
+        for length in 2..=pssm.len().min(Self::MAX_WINDOW) {
+            // use the most informative window of each length
+            let start = (0..=pssm.len() - length)
+                .max_by(|&i, &j| {
+                    let x: f32 = information[i..i + length].iter().sum();
+                    let y: f32 = information[j..j + length].iter().sum();
+                    x.partial_cmp(&y)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then(j.cmp(&i))
+                })
+                .unwrap();
+            let rest = total - maxima[start..start + length].iter().sum::<f32>();
+            let window = ScoringMatrix::<A>::new(
+                Background::uniform(),
+                DenseMatrix::from_rows((start..start + length).map(|j| &weights[j])),
+            );
+            let distribution = ScoreDistribution::new(&window, None, Self::GRANULARITY);
+            let words = distribution.pvalue(cutoff - rest) * symbols.powi(length as i32);
+            if words <= Self::MAX_WORDS {
+                best = (start, length);
+            }
+        }
+        best
+    }
+
+    /// Enumerate the words of a window that may be part of a site.
+    fn enumerate<F>(pssm: &ScoringMatrix<A>, start: usize, length: usize, cutoff: f32, mut f: F)
+    where
+        F: FnMut(&[usize]),
+    {
+        let weights = pssm.weights();
+        // maximum score reachable after each column of the window, visiting
+        // the columns outside of the window last
+        let columns = (start..start + length)
+            .chain(0..start)
+            .chain(start + length..pssm.len())
+            .collect::<Vec<usize>>();
+        let bounds = bounds(pssm, &columns);
+
+        let mut word = Vec::with_capacity(length);
+        let mut partials = vec![0.0; length + 1];
+        let mut next = 0;
+        loop {
+            let t = word.len();
+            if next < A::K::USIZE {
+                let partial = partials[t] + weights[start + t][next];
+                if partial + bounds[t + 1] >= cutoff {
+                    word.push(next);
+                    partials[t + 1] = partial;
+                    if word.len() == length {
+                        f(&word);
+                    } else {
+                        next = 0;
+                        continue;
+                    }
+                    word.pop();
+                }
+                next += 1;
+            } else if let Some(x) = word.pop() {
+                next = x + 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The number of matrices of the scanner.
+    pub fn len(&self) -> usize {
+        self.scanners.len()
+    }
+
+    /// Check whether the scanner has no matrices.
+    pub fn is_empty(&self) -> bool {
+        self.scanners.is_empty()
+    }
+
+    /// Return the positions with a score equal to or greater than the threshold.
+    ///
+    /// Positions are returned for each matrix, in increasing order. An
+    /// empty matrix has a score of zero at every position, including the
+    /// end of the sequence, as with [`Lookahead::threshold`].
+    pub fn scan(&self, sequence: &EncodedSequence<A>) -> Vec<Vec<usize>> {
+        let automaton = &self.automaton;
+        let mut hits = vec![Vec::new(); self.scanners.len()];
+        for (motif, scanner) in self.scanners.iter().enumerate() {
+            if self.windows[motif] == 0 {
+                hits[motif] = scanner.threshold(sequence, self.thresholds[motif]);
+            }
+        }
+        let mut node = 0;
+        for (end, x) in sequence.iter().into_iter().enumerate() {
+            node = automaton.transitions[node * automaton.symbols + x.as_index()] as usize;
+            let mut n = if automaton.outputs[node].is_empty() {
+                automaton.links[node]
+            } else {
+                node as u32
+            };
+            while n != NONE {
+                for &(motif, offset) in automaton.outputs[n as usize].iter() {
+                    let motif = motif as usize;
+                    let scanner = &self.scanners[motif];
+                    let length = self.windows[motif] + offset as usize;
+                    if end + 1 < length {
+                        continue;
+                    }
+                    let i = end + 1 - length;
+                    if i + scanner.pssm.len() <= sequence.len()
+                        && scanner.score(sequence, i) >= self.thresholds[motif]
+                    {
+                        hits[motif].push(i);
+                    }
+                }
+                n = automaton.links[n as usize];
+            }
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use typenum::consts::U32;
+
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::pli::Pipeline;
+    use crate::pli::Score;
+    use crate::pli::Threshold;
+    use crate::pwm::CountMatrix;
+    use crate::util::fixtures::pssm;
+    use crate::util::fixtures::sequence;
+
+    #[test]
+    fn test_threshold() {
+        let pssm = pssm();
+        let mut seq = sequence(2000, 42);
+        seq = seq
+            .slice(0..500)
+            .concat(&EncodedSequence::encode(&pssm.consensus()).unwrap())
+            .concat(&seq.slice(515..2000));
+
+        let lookahead = Lookahead::new(&pssm);
+        let pli = Pipeline::generic();
+        let mut striped = seq.to_striped::<U32>();
+        striped.configure(&pssm);
+        let scores = pli.score(&striped, &pssm);
+
+        // include thresholds equal to observed scores to check boundaries
+        let mut sorted = scores.iter().cloned().collect::<Vec<f32>>();
+        sorted.sort_by(|x, y| y.partial_cmp(x).unwrap());
+        let thresholds = [pssm.max_score(), 10.0, 0.0, -20.0, sorted[0], sorted[3]];
+        for &threshold in thresholds.iter() {
+            let mut expected = pli.threshold(&scores, threshold);
+            expected.sort();
+            assert_eq!(lookahead.threshold(&seq, threshold), expected);
+        }
+        assert_eq!(lookahead.threshold(&seq, pssm.max_score()), vec![500]);
+    }
+
+    #[test]
+    fn test_threshold_short() {
+        let pssm = pssm();
+        let lookahead = Lookahead::new(&pssm);
+        let seq = EncodedSequence::encode("GTTGA").unwrap();
+        assert!(lookahead.threshold(&seq, -100.0).is_empty());
+    }
+
+    #[test]
+    fn test_multi_scanner() {
+        let matrices = [
+            pssm(),
+            pssm().reverse_complement(),
+            CountMatrix::from_sequences(
+                ["TTGACGCA", "TTGACGTA"]
+                    .iter()
+                    .map(|s| EncodedSequence::encode(s).unwrap()),
+            )
+            .unwrap()
+            .to_freq(0.25)
+            .to_scoring(None),
+        ];
+        let seq = sequence(5000, 7)
+            .concat(&EncodedSequence::encode(&matrices[0].consensus()).unwrap())
+            .concat(&EncodedSequence::encode("TTGACGCA").unwrap())
+            .concat(&sequence(100, 1));
+
+        let pli = Pipeline::generic();
+        let mut striped = seq.to_striped::<U32>();
+        striped.configure_wrap(14);
+        let scores = matrices
+            .iter()
+            .map(|pssm| pli.score(&striped, pssm))
+            .collect::<Vec<_>>();
+        let thresholds = [
+            [10.0, 10.0, 8.0],
+            [15.0, 5.0, 12.0],
+            [0.0, -5.0, 2.0],
+            [
+                matrices[0].max_score(),
+                scores[1].iter().cloned().fold(f32::NEG_INFINITY, f32::max),
+                matrices[2].max_score(),
+            ],
+        ];
+        for thresholds in thresholds.iter() {
+            let scanner = MultiScanner::new(&matrices, thresholds);
+            assert_eq!(scanner.len(), 3);
+            let hits = scanner.scan(&seq);
+            for (k, scores) in scores.iter().enumerate() {
+                let mut expected = pli.threshold(scores, thresholds[k]);
+                expected.sort();
+                assert_eq!(hits[k], expected, "motif {} at {}", k, thresholds[k]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_scanner_empty() {
+        let empty = ScoringMatrix::<Dna>::new(Background::uniform(), DenseMatrix::new(0));
+        let matrices = [empty.clone(), pssm(), empty];
+        let seq = sequence(100, 3);
+        let scanner = MultiScanner::new(&matrices, &[0.0, 10.0, 1.0]);
+        let hits = scanner.scan(&seq);
+        let lookahead = Lookahead::new(&matrices[0]);
+        assert_eq!(hits[0], lookahead.threshold(&seq, 0.0));
+        assert_eq!(hits[0].len(), seq.len() + 1);
+        assert_eq!(hits[1], Lookahead::new(&matrices[1]).threshold(&seq, 10.0));
+        assert!(hits[2].is_empty());
+    }
+}
@@ -3,18 +3,85 @@
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::ops::Index;
+use std::ops::Range;
+use std::slice::SliceIndex;
 use std::str::FromStr;
 
 use typenum::marker_traits::NonZero;
 use typenum::marker_traits::Unsigned;
 
 use super::abc::Alphabet;
+use super::abc::ComplementableAlphabet;
+use super::abc::Dna;
+use super::abc::Nucleotide;
 use super::abc::Symbol;
 use super::dense::DenseMatrix;
 use super::err::InvalidSymbol;
 use super::num::StrictlyPositive;
 use super::pwm::ScoringMatrix;
 
+// --- Strand ------------------------------------------------------------------
+
+/// A strand of a double-stranded sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strand {
+    /// The direct strand, as given in the sequence.
+    Direct,
+    /// The reverse strand, obtained by reverse-complementing the sequence.
+    Reverse,
+}
+
+/// A function computing the reverse strand of a sequence.
+///
+/// Analyses generic over any [`Alphabet`] store this function pointer to
+/// scan the reverse strand, which can only be computed for a
+/// [`ComplementableAlphabet`].
+pub(crate) type ReverseStrand<A> = fn(&EncodedSequence<A>) -> EncodedSequence<A>;
+
+/// Get the function computing the reverse strand, if both strands are used.
+pub(crate) fn reverse_strand<A: ComplementableAlphabet>(
+    both_strands: bool,
+) -> Option<ReverseStrand<A>> {
+    if both_strands {
+        Some(EncodedSequence::reverse_complement)
+    } else {
+        None
+    }
+}
+
+/// The striped strands of a sequence, wrapped for the same motif length.
+pub(crate) struct Strands<A: Alphabet, C: StrictlyPositive> {
+    /// The length of the sequence.
+    pub(crate) length: usize,
+    /// The direct strand.
+    pub(crate) direct: StripedSequence<A, C>,
+    /// The reverse strand, if both strands are used.
+    pub(crate) reverse: Option<StripedSequence<A, C>>,
+}
+
+impl<A: Alphabet, C: StrictlyPositive> Strands<A, C> {
+    /// Stripe the strands of a sequence, with the given wrap.
+    pub(crate) fn new(
+        sequence: &EncodedSequence<A>,
+        wrap: usize,
+        reverse: Option<ReverseStrand<A>>,
+    ) -> Self {
+        let mut direct = sequence.to_striped::<C>();
+        direct.configure_wrap(wrap);
+        let reverse = reverse.map(|f| {
+            let mut reverse = f(sequence).to_striped::<C>();
+            reverse.configure_wrap(wrap);
+            reverse
+        });
+        Self {
+            length: sequence.len(),
+            direct,
+            reverse,
+        }
+    }
+}
+
 // --- EncodedSequence ---------------------------------------------------------
 
 /// A biological sequence encoded with an alphabet.
@@ -56,18 +123,37 @@ impl<A: Alphabet> EncodedSequence<A> {
 
     /// Convert the encoded sequence to a striped matrix.
     pub fn to_striped<C: Unsigned + NonZero>(&self) -> StripedSequence<A, C> {
-        let length = self.data.len();
-        let n = (length + (C::USIZE - 1)) / C::USIZE;
-        let mut data = DenseMatrix::new(n);
-        for (i, &x) in self.data.iter().enumerate() {
-            data[i % n][i / n] = x;
-        }
-        StripedSequence {
-            alphabet: std::marker::PhantomData,
-            data,
-            length,
-            wrap: 0,
-        }
+        StripedSequence::from_symbols(self.data.len(), self.data.iter().cloned())
+    }
+
+    /// Convert a region of the encoded sequence to a striped matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the sequence bounds.
+    pub fn to_striped_range<C: Unsigned + NonZero>(
+        &self,
+        range: Range<usize>,
+    ) -> StripedSequence<A, C> {
+        let symbols = &self.data[range];
+        StripedSequence::from_symbols(symbols.len(), symbols.iter().cloned())
+    }
+
+    /// Extract a region of the encoded sequence into a new sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the sequence bounds.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        Self::new(self.data[range].to_vec())
+    }
+
+    /// Create a new sequence by appending `other` to the end of this sequence.
+    pub fn concat(&self, other: &Self) -> Self {
+        let mut data = Vec::with_capacity(self.len() + other.len());
+        data.extend_from_slice(&self.data);
+        data.extend_from_slice(&other.data);
+        Self::new(data)
     }
 
     /// Convert the encoded sequence back to its textual representation.
@@ -80,6 +166,13 @@ impl<A: Alphabet> EncodedSequence<A> {
     }
 }
 
+impl<A: ComplementableAlphabet> EncodedSequence<A> {
+    /// Get the reverse-complement of this sequence.
+    pub fn reverse_complement(&self) -> Self {
+        Self::new(self.data.iter().rev().map(|&s| A::complement(s)).collect())
+    }
+}
+
 impl<A: Alphabet> AsRef<EncodedSequence<A>> for EncodedSequence<A> {
     fn as_ref(&self) -> &Self {
         self
@@ -107,6 +200,18 @@ impl<A: Alphabet> Display for EncodedSequence<A> {
     }
 }
 
+impl<A: Alphabet> Extend<A::Symbol> for EncodedSequence<A> {
+    fn extend<I: IntoIterator<Item = A::Symbol>>(&mut self, iter: I) {
+        self.data.extend(iter)
+    }
+}
+
+impl<A: Alphabet> FromIterator<A::Symbol> for EncodedSequence<A> {
+    fn from_iter<I: IntoIterator<Item = A::Symbol>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
 impl<A: Alphabet> FromStr for EncodedSequence<A> {
     type Err = InvalidSymbol;
     fn from_str(seq: &str) -> Result<Self, Self::Err> {
@@ -120,6 +225,14 @@ impl<A: Alphabet> From<Vec<A::Symbol>> for EncodedSequence<A> {
     }
 }
 
+impl<A: Alphabet, I: SliceIndex<[A::Symbol]>> Index<I> for EncodedSequence<A> {
+    type Output = I::Output;
+    #[inline]
+    fn index(&self, index: I) -> &Self::Output {
+        self.data.index(index)
+    }
+}
+
 impl<'a, A: Alphabet> IntoIterator for &'a EncodedSequence<A> {
     type Item = &'a A::Symbol;
     type IntoIter = std::slice::Iter<'a, A::Symbol>;
@@ -129,6 +242,116 @@ impl<'a, A: Alphabet> IntoIterator for &'a EncodedSequence<A> {
     }
 }
 
+// --- AlignedSequence ---------------------------------------------------------
+
+/// A row of a multiple sequence alignment, possibly containing gaps.
+///
+/// Gaps are encoded as `None`, and can be given as either `-` or `.`
+/// characters in a textual representation.
+#[derive(Clone, Debug)]
+pub struct AlignedSequence<A: Alphabet> {
+    alphabet: std::marker::PhantomData<A>,
+    data: Vec<Option<A::Symbol>>,
+}
+
+impl<A: Alphabet> AlignedSequence<A> {
+    /// Create a new aligned sequence.
+    pub fn new(data: Vec<Option<A::Symbol>>) -> Self {
+        Self {
+            data,
+            alphabet: std::marker::PhantomData,
+        }
+    }
+
+    /// Create a new aligned sequence from a textual representation.
+    pub fn encode(sequence: &str) -> Result<Self, InvalidSymbol> {
+        sequence
+            .chars()
+            .map(|c| match c {
+                '-' | '.' => Ok(None),
+                _ => A::Symbol::from_char(c).map(Some),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+
+    /// Return the number of columns in the aligned sequence, including gaps.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check whether the aligned sequence is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Return the number of gaps in the aligned sequence.
+    pub fn gaps(&self) -> usize {
+        self.data.iter().filter(|x| x.is_none()).count()
+    }
+
+    /// Iterate over the columns of the aligned sequence.
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, Option<A::Symbol>> {
+        self.data.iter()
+    }
+
+    /// Get the ungapped sequence by removing all gaps.
+    pub fn to_ungapped(&self) -> EncodedSequence<A> {
+        self.data.iter().flatten().cloned().collect()
+    }
+}
+
+impl<A: Alphabet> AsRef<AlignedSequence<A>> for AlignedSequence<A> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<A: Alphabet> Display for AlignedSequence<A> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for x in self.data.iter() {
+            match x {
+                Some(c) => write!(f, "{}", c.as_char())?,
+                None => f.write_str("-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<A: Alphabet> From<EncodedSequence<A>> for AlignedSequence<A> {
+    fn from(sequence: EncodedSequence<A>) -> Self {
+        Self::new(sequence.data.into_iter().map(Some).collect())
+    }
+}
+
+impl<A: Alphabet> FromStr for AlignedSequence<A> {
+    type Err = InvalidSymbol;
+    fn from_str(seq: &str) -> Result<Self, Self::Err> {
+        Self::encode(seq)
+    }
+}
+
+impl<A: Alphabet> Index<usize> for AlignedSequence<A> {
+    type Output = Option<A::Symbol>;
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.data.index(index)
+    }
+}
+
+impl<'a, A: Alphabet> IntoIterator for &'a AlignedSequence<A> {
+    type Item = &'a Option<A::Symbol>;
+    type IntoIter = std::slice::Iter<'a, Option<A::Symbol>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
 // --- StripedSequence ---------------------------------------------------------
 
 /// An encoded sequence stored in a striped matrix with a fixed column count.
@@ -141,6 +364,24 @@ pub struct StripedSequence<A: Alphabet, C: StrictlyPositive> {
 }
 
 impl<A: Alphabet, C: StrictlyPositive> StripedSequence<A, C> {
+    /// Create a new striped sequence from an iterator over `length` symbols.
+    fn from_symbols<I>(length: usize, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = A::Symbol>,
+    {
+        let n = (length + (C::USIZE - 1)) / C::USIZE;
+        let mut data = DenseMatrix::new(n);
+        for (i, x) in symbols.into_iter().take(length).enumerate() {
+            data[i % n][i / n] = x;
+        }
+        StripedSequence {
+            alphabet: std::marker::PhantomData,
+            data,
+            length,
+            wrap: 0,
+        }
+    }
+
     /// Create a new striped sequence from a textual representation.
     pub fn encode(sequence: &str) -> Result<Self, InvalidSymbol> {
         let length = sequence.len();
@@ -198,6 +439,277 @@ impl<A: Alphabet, C: StrictlyPositive> FromStr for StripedSequence<A, C> {
     }
 }
 
+// --- PackedSequence ----------------------------------------------------------
+
+/// The number of nucleotides stored in each word of a packed sequence.
+const PACKED_BASES: usize = u64::BITS as usize / 2;
+
+/// The nucleotides corresponding to each 2-bit code of a packed sequence.
+const PACKED_SYMBOLS: [Nucleotide; 4] =
+    [Nucleotide::A, Nucleotide::C, Nucleotide::T, Nucleotide::G];
+
+/// A DNA sequence stored with 2 bits per nucleotide.
+///
+/// The four deoxyribonucleotides are packed into 64-bit words, so that a
+/// packed sequence takes a quarter of the memory of the equivalent
+/// [`EncodedSequence`]. Unknown bases do not fit in 2 bits: their positions
+/// are recorded in a sparse mask of sorted, non-overlapping ranges, which
+/// stays small for assemblies where unknown bases come in long runs.
+///
+/// A packed sequence cannot be scored directly, but it can be converted
+/// into [`StripedSequence`] blocks with [`PackedSequence::blocks`], so that
+/// a whole genome can be scanned without ever being fully unpacked.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackedSequence {
+    data: Vec<u64>,
+    length: usize,
+    unknown: Vec<Range<usize>>,
+}
+
+impl PackedSequence {
+    /// Create a new empty packed sequence.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new empty packed sequence with room for `capacity` nucleotides.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(capacity / PACKED_BASES + 1),
+            length: 0,
+            unknown: Vec::new(),
+        }
+    }
+
+    /// Create a new packed sequence from a textual representation.
+    pub fn encode(sequence: &str) -> Result<Self, InvalidSymbol> {
+        let mut packed = Self::with_capacity(sequence.len());
+        for c in sequence.chars() {
+            packed.push(Nucleotide::from_char(c)?);
+        }
+        Ok(packed)
+    }
+
+    /// Return the number of nucleotides in the sequence.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Check whether the sequence is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The sorted ranges of unknown nucleotides in the sequence.
+    #[inline]
+    pub fn unknown(&self) -> &[Range<usize>] {
+        &self.unknown
+    }
+
+    /// Append a nucleotide to the end of the sequence.
+    pub fn push(&mut self, nucleotide: Nucleotide) {
+        let i = self.length;
+        if i == self.data.len() * PACKED_BASES {
+            self.data.push(0);
+        }
+        match nucleotide {
+            Nucleotide::N => match self.unknown.last_mut() {
+                Some(r) if r.end == i => r.end += 1,
+                _ => self.unknown.push(i..i + 1),
+            },
+            other => {
+                self.data[i / PACKED_BASES] |= (other as u64) << (2 * (i % PACKED_BASES));
+            }
+        }
+        self.length += 1;
+    }
+
+    /// Get the nucleotide at the given position, if any.
+    pub fn get(&self, index: usize) -> Option<Nucleotide> {
+        if index >= self.length {
+            return None;
+        }
+        let k = self.unknown.partition_point(|r| r.end <= index);
+        match self.unknown.get(k) {
+            Some(r) if r.start <= index => Some(Nucleotide::N),
+            _ => Some(self.get_packed(index)),
+        }
+    }
+
+    /// Get the nucleotide stored in the 2-bit array, ignoring the mask.
+    #[inline]
+    fn get_packed(&self, index: usize) -> Nucleotide {
+        let word = self.data[index / PACKED_BASES];
+        PACKED_SYMBOLS[((word >> (2 * (index % PACKED_BASES))) & 0b11) as usize]
+    }
+
+    /// Iterate over the nucleotides of the sequence.
+    #[inline]
+    pub fn iter(&self) -> PackedIter<'_> {
+        self.iter_range(0..self.length)
+    }
+
+    /// Iterate over the nucleotides in a region of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the sequence bounds.
+    pub fn iter_range(&self, range: Range<usize>) -> PackedIter<'_> {
+        assert!(range.start <= range.end && range.end <= self.length);
+        PackedIter {
+            mask: self.unknown.partition_point(|r| r.end <= range.start),
+            indices: range,
+            sequence: self,
+        }
+    }
+
+    /// Unpack the sequence into an encoded sequence.
+    pub fn to_encoded(&self) -> EncodedSequence<Dna> {
+        EncodedSequence::new(self.iter().collect())
+    }
+
+    /// Convert the packed sequence to a striped matrix.
+    pub fn to_striped<C: StrictlyPositive>(&self) -> StripedSequence<Dna, C> {
+        self.to_striped_range(0..self.length)
+    }
+
+    /// Convert a region of the packed sequence to a striped matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the sequence bounds.
+    pub fn to_striped_range<C: StrictlyPositive>(
+        &self,
+        range: Range<usize>,
+    ) -> StripedSequence<Dna, C> {
+        StripedSequence::from_symbols(range.len(), self.iter_range(range))
+    }
+
+    /// Iterate over consecutive striped blocks of the sequence.
+    ///
+    /// Each block starts `length` positions after the previous one, and
+    /// extends `overlap` positions into the next one. When scanning a motif
+    /// of length `m`, use an overlap of `m - 1`: every window of the sequence
+    /// then starts in the first `length` positions of exactly one block.
+    /// Blocks are yielded along with the sequence position of their first
+    /// nucleotide; the last block may be shorter than the motif.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is zero.
+    pub fn blocks<C: StrictlyPositive>(&self, length: usize, overlap: usize) -> Blocks<'_, C> {
+        assert!(length > 0, "block length must be strictly positive");
+        Blocks {
+            sequence: self,
+            length,
+            overlap,
+            start: 0,
+            _columns: std::marker::PhantomData,
+        }
+    }
+}
+
+impl Display for PackedSequence {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for c in self.iter() {
+            write!(f, "{}", c.as_char())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PackedSequence {
+    type Err = InvalidSymbol;
+    fn from_str(seq: &str) -> Result<Self, Self::Err> {
+        Self::encode(seq)
+    }
+}
+
+impl FromIterator<Nucleotide> for PackedSequence {
+    fn from_iter<I: IntoIterator<Item = Nucleotide>>(iter: I) -> Self {
+        let it = iter.into_iter();
+        let mut packed = Self::with_capacity(it.size_hint().0);
+        for x in it {
+            packed.push(x);
+        }
+        packed
+    }
+}
+
+impl From<&EncodedSequence<Dna>> for PackedSequence {
+    fn from(encoded: &EncodedSequence<Dna>) -> Self {
+        encoded.data.iter().cloned().collect()
+    }
+}
+
+impl From<&PackedSequence> for EncodedSequence<Dna> {
+    fn from(packed: &PackedSequence) -> Self {
+        packed.to_encoded()
+    }
+}
+
+impl<'a> IntoIterator for &'a PackedSequence {
+    type Item = Nucleotide;
+    type IntoIter = PackedIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the nucleotides of a [`PackedSequence`].
+#[derive(Clone, Debug)]
+pub struct PackedIter<'a> {
+    sequence: &'a PackedSequence,
+    indices: Range<usize>,
+    mask: usize,
+}
+
+impl<'a> Iterator for PackedIter<'a> {
+    type Item = Nucleotide;
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.indices.next()?;
+        let unknown = &self.sequence.unknown;
+        while self.mask < unknown.len() && unknown[self.mask].end <= i {
+            self.mask += 1;
+        }
+        match unknown.get(self.mask) {
+            Some(r) if r.start <= i => Some(Nucleotide::N),
+            _ => Some(self.sequence.get_packed(i)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for PackedIter<'a> {}
+
+/// An iterator over the striped blocks of a [`PackedSequence`].
+#[derive(Clone, Debug)]
+pub struct Blocks<'a, C: StrictlyPositive> {
+    sequence: &'a PackedSequence,
+    length: usize,
+    overlap: usize,
+    start: usize,
+    _columns: std::marker::PhantomData<C>,
+}
+
+impl<'a, C: StrictlyPositive> Iterator for Blocks<'a, C> {
+    type Item = (usize, StripedSequence<Dna, C>);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start >= self.sequence.len() {
+            return None;
+        }
+        let start = self.start;
+        let end = (start + self.length + self.overlap).min(self.sequence.len());
+        self.start += self.length;
+        Some((start, self.sequence.to_striped_range(start..end)))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use typenum::consts::U2;
@@ -235,4 +747,101 @@ mod test {
         assert_eq!(&striped.data[2], &[G, A, N, N]);
         assert_eq!(&striped.data[3], &[C, N, N, N]);
     }
+
+    #[test]
+    fn test_packed_roundtrip() {
+        let text = "ATGCANNNNTTGACCGTAGGCATTACGATCGATCAGNA";
+        let packed = PackedSequence::from_str(text).unwrap();
+        assert_eq!(packed.len(), text.len());
+        assert_eq!(packed.unknown(), &[5..9, 36..37]);
+        assert_eq!(packed.get(4), Some(A));
+        assert_eq!(packed.get(5), Some(N));
+        assert_eq!(packed.get(9), Some(T));
+        assert_eq!(packed.get(text.len()), None);
+        assert_eq!(packed.to_string(), text);
+
+        let encoded = EncodedSequence::<Dna>::from_str(text).unwrap();
+        assert_eq!(PackedSequence::from(&encoded), packed);
+        assert_eq!(packed.to_encoded().to_string(), text);
+        assert_eq!(
+            packed.iter_range(3..11).collect::<Vec<_>>(),
+            vec![C, A, N, N, N, N, T, T]
+        );
+    }
+
+    #[test]
+    fn test_packed_stripe() {
+        let packed = PackedSequence::from_str("ATGCA").unwrap();
+        let striped = packed.to_striped::<U4>();
+        assert_eq!(striped.length, 5);
+        assert_eq!(&striped.data[0], &[A, G, A, N]);
+        assert_eq!(&striped.data[1], &[T, C, N, N]);
+
+        let striped = packed.to_striped_range::<U2>(1..4);
+        assert_eq!(striped.length, 3);
+        assert_eq!(&striped.data[0], &[T, C]);
+        assert_eq!(&striped.data[1], &[G, N]);
+    }
+
+    #[test]
+    fn test_packed_blocks() {
+        let packed = PackedSequence::from_str("ATGCANNTTGA").unwrap();
+        let blocks = packed.blocks::<U2>(4, 2).collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].0, 0);
+        assert_eq!(blocks[0].1.length, 6);
+        assert_eq!(&blocks[0].1.data[0], &[A, C]);
+        assert_eq!(blocks[1].0, 4);
+        assert_eq!(blocks[1].1.length, 6);
+        assert_eq!(&blocks[1].1.data[0], &[A, T]);
+        assert_eq!(&blocks[1].1.data[1], &[N, T]);
+        assert_eq!(blocks[2].0, 8);
+        assert_eq!(blocks[2].1.length, 3);
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        let seq = EncodedSequence::<Dna>::from_str("ATGCNA").unwrap();
+        let rc = seq.reverse_complement();
+        assert_eq!(rc.to_string(), "TNGCAT");
+        assert_eq!(rc.reverse_complement().to_string(), "ATGCNA");
+    }
+
+    #[test]
+    fn test_slice_concat() {
+        let seq = EncodedSequence::<Dna>::from_str("ATGCNA").unwrap();
+        assert_eq!(seq[2], G);
+        assert_eq!(&seq[1..4], &[T, G, C]);
+        assert_eq!(seq.slice(1..4).to_string(), "TGC");
+        assert_eq!(seq.slice(3..3).len(), 0);
+
+        let other = EncodedSequence::<Dna>::from_str("GG").unwrap();
+        assert_eq!(seq.concat(&other).to_string(), "ATGCNAGG");
+
+        let mut extended = seq.slice(0..2);
+        extended.extend(other.iter().into_iter().cloned());
+        assert_eq!(extended.to_string(), "ATGG");
+    }
+
+    #[test]
+    fn test_stripe_range() {
+        let seq = EncodedSequence::<Dna>::from_str("TTATGCATT").unwrap();
+        let striped = seq.to_striped_range::<U4>(2..7);
+        assert_eq!(striped.length, 5);
+        assert_eq!(striped.data.rows(), 2);
+        assert_eq!(&striped.data[0], &[A, G, A, N]);
+        assert_eq!(&striped.data[1], &[T, C, N, N]);
+    }
+
+    #[test]
+    fn test_aligned_sequence() {
+        let seq = AlignedSequence::<Dna>::from_str("AT-G.C").unwrap();
+        assert_eq!(seq.len(), 6);
+        assert_eq!(seq.gaps(), 2);
+        assert_eq!(seq[1], Some(T));
+        assert_eq!(seq[2], None);
+        assert_eq!(seq.to_string(), "AT-G-C");
+        assert_eq!(seq.to_ungapped().to_string(), "ATGC");
+        assert!(AlignedSequence::<Dna>::from_str("AT_G").is_err());
+    }
 }
@@ -0,0 +1,469 @@
+//! Statistical tests and score distributions.
+//!
+//! P-values of enrichment tests are computed in log-space, since the
+//! enrichment of a motif in a large set of sequences routinely yields
+//! p-values far below the smallest positive `f64`.
+//!
+//! The [`ScoreDistribution`] gives the distribution of the scores of a
+//! [`ScoringMatrix`] for random sequences sampled from a [`Background`],
+//! and can be used to convert scores to p-values and back.
+
+use super::abc::Alphabet;
+use super::abc::Background;
+use super::pwm::ScoringMatrix;
+
+/// Compute the logarithm of the sum of exponentials of the given values.
+fn log_sum_exp<I: IntoIterator<Item = f64>>(values: I) -> f64 {
+    let values = values.into_iter().collect::<Vec<f64>>();
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|x| (x - max).exp()).sum::<f64>().ln()
+}
+
+/// Compute the natural logarithm of the gamma function.
+///
+/// Uses the Lanczos approximation (g = 7, n = 9), which is accurate to
+/// about 15 significant digits for positive arguments.
+pub fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection formula
+        let pi = std::f64::consts::PI;
+        (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x)
+    } else {
+        let x = x - 1.0;
+        let t = x + G + 0.5;
+        let a = COEFFICIENTS
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold(COEFFICIENTS[0], |a, (i, &c)| a + c / (x + i as f64));
+        0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+    }
+}
+
+/// Compute the natural logarithm of the binomial coefficient `n` choose `k`.
+pub fn ln_choose(n: usize, k: usize) -> f64 {
+    if k > n {
+        f64::NEG_INFINITY
+    } else {
+        ln_gamma(n as f64 + 1.0) - ln_gamma(k as f64 + 1.0) - ln_gamma((n - k) as f64 + 1.0)
+    }
+}
+
+/// Compute the one-sided Fisher's exact test for a 2x2 contingency table.
+///
+/// The table is given as `a` and `b`, the number of primary sequences with
+/// and without a feature, and `c` and `d`, the number of control sequences
+/// with and without the feature. Returns the natural logarithm of the
+/// probability of observing at least `a` primary sequences with the
+/// feature under the hypergeometric distribution.
+pub fn fisher_exact(a: usize, b: usize, c: usize, d: usize) -> f64 {
+    let n = a + b + c + d;
+    let with = a + c;
+    let primary = a + b;
+    let ln_total = ln_choose(n, primary);
+    let ln_p = log_sum_exp(
+        (a..=with.min(primary))
+            .map(|x| ln_choose(with, x) + ln_choose(n - with, primary - x) - ln_total),
+    );
+    ln_p.min(0.0)
+}
+
+/// Compute the one-sided binomial test for `k` successes in `n` trials.
+///
+/// Returns the natural logarithm of the probability of observing at least
+/// `k` successes in `n` trials with a success probability of `p`.
+pub fn binomial_test(k: usize, n: usize, p: f64) -> f64 {
+    if k == 0 {
+        return 0.0;
+    } else if k > n || p <= 0.0 {
+        return f64::NEG_INFINITY;
+    } else if p >= 1.0 {
+        return 0.0;
+    }
+    let (ln_p, ln_q) = (p.ln(), (1.0 - p).ln());
+    let ln_pvalue =
+        log_sum_exp((k..=n).map(|i| ln_choose(n, i) + i as f64 * ln_p + (n - i) as f64 * ln_q));
+    ln_pvalue.min(0.0)
+}
+
+/// Compute the natural logarithm of the standard normal survival function.
+///
+/// Uses the complementary error function approximation from Numerical
+/// Recipes, which has a relative error below 1.2e-7 everywhere and is
+/// computed in log-space, so that it remains accurate far in the tail.
+pub fn ln_normal_sf(z: f64) -> f64 {
+    let x = z / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let ln_erfc = t.ln() - x * x - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    if x >= 0.0 {
+        ln_erfc - std::f64::consts::LN_2
+    } else {
+        (1.0 - 0.5 * ln_erfc.exp()).ln()
+    }
+}
+
+/// Adjust p-values for multiple testing with the Benjamini-Hochberg procedure.
+///
+/// Both the input p-values and the returned adjusted p-values are given as
+/// natural logarithms, in the same order.
+pub fn benjamini_hochberg(log_pvalues: &[f64]) -> Vec<f64> {
+    let m = log_pvalues.len();
+    let mut order = (0..m).collect::<Vec<usize>>();
+    order.sort_by(|&i, &j| log_pvalues[i].total_cmp(&log_pvalues[j]));
+    let mut adjusted = vec![0.0; m];
+    let mut min = 0.0f64;
+    for (rank, &i) in order.iter().enumerate().rev() {
+        let q = log_pvalues[i] + (m as f64).ln() - ((rank + 1) as f64).ln();
+        min = min.min(q);
+        adjusted[i] = min;
+    }
+    adjusted
+}
+
+// --- ScoreDistribution -------------------------------------------------------
+
+/// The exact distribution of the scores of a scoring matrix.
+///
+/// Weights are rounded to a multiple of the granularity, so that the scores
+/// take a finite number of values, and the distribution of the scores of
+/// random sequences is computed with dynamic programming, in the spirit of
+/// Staden (1989) and TFM-Pvalue (Touzet & Varré, 2007). A smaller
+/// granularity gives more accurate results, at the cost of more memory.
+///
+/// Symbols with an infinitely negative weight, such as symbols never
+/// observed in a motif built without pseudocounts, never reach a finite
+/// score: their probability is kept out of the probability mass function,
+/// which may then sum to less than one.
+#[derive(Clone, Debug)]
+pub struct ScoreDistribution {
+    granularity: f64,
+    /// The minimum score, in multiples of the granularity.
+    offset: i64,
+    /// The probability of each score, starting from the minimum score.
+    pmf: Vec<f64>,
+    /// The probability of each score or a higher score.
+    sf: Vec<f64>,
+}
+
+impl ScoreDistribution {
+    /// Compute the score distribution of a scoring matrix.
+    ///
+    /// Random sequences are sampled from the given background, or from the
+    /// background of the scoring matrix if none is given.
+    ///
+    /// # Panics
+    /// Panics if `granularity` is not strictly positive.
+    pub fn new<A, B>(pssm: &ScoringMatrix<A>, background: B, granularity: f64) -> Self
+    where
+        A: Alphabet,
+        B: Into<Option<Background<A>>>,
+    {
+        assert!(granularity > 0.0, "granularity must be strictly positive");
+        let background = background
+            .into()
+            .unwrap_or_else(|| pssm.background().clone());
+        let frequencies = background.frequencies();
+
+        let mut offset = 0;
+        let mut pmf = vec![1.0];
+        for row in pssm.weights().iter() {
+            let weights = row
+                .iter()
+                .zip(frequencies)
+                .filter(|(w, &f)| f > 0.0 && w.is_finite())
+                .map(|(&w, &f)| ((w as f64 / granularity).round() as i64, f as f64))
+                .collect::<Vec<_>>();
+            let min = weights.iter().map(|x| x.0).min().unwrap_or(0);
+            let max = weights.iter().map(|x| x.0).max().unwrap_or(0);
+            let mut next = vec![0.0; pmf.len() + (max - min) as usize];
+            for (i, &p) in pmf.iter().enumerate() {
+                if p > 0.0 {
+                    for &(w, f) in weights.iter() {
+                        next[i + (w - min) as usize] += p * f;
+                    }
+                }
+            }
+            offset += min;
+            pmf = next;
+        }
+
+        let mut sf = pmf.clone();
+        for i in (0..sf.len().saturating_sub(1)).rev() {
+            sf[i] += sf[i + 1];
+        }
+        Self {
+            granularity,
+            offset,
+            pmf,
+            sf,
+        }
+    }
+
+    /// The granularity used to discretize the scores.
+    pub fn granularity(&self) -> f64 {
+        self.granularity
+    }
+
+    /// The lowest reachable score.
+    pub fn min_score(&self) -> f32 {
+        (self.offset as f64 * self.granularity) as f32
+    }
+
+    /// The highest reachable score.
+    pub fn max_score(&self) -> f32 {
+        ((self.offset + self.pmf.len() as i64 - 1) as f64 * self.granularity) as f32
+    }
+
+    /// Iterate over the reachable scores and their probabilities.
+    pub fn iter(&self) -> impl Iterator<Item = (f32, f64)> + '_ {
+        self.pmf.iter().enumerate().map(|(i, &p)| {
+            let score = (self.offset + i as i64) as f64 * self.granularity;
+            (score as f32, p)
+        })
+    }
+
+    /// Get the index of a discretized score, clamped just outside the bounds.
+    ///
+    /// Clamping before the conversion to an integer keeps infinite scores
+    /// from overflowing when subtracting the offset.
+    fn index(&self, x: f64) -> i64 {
+        (x - self.offset as f64).clamp(-1.0, self.pmf.len() as f64) as i64
+    }
+
+    /// Get the index of the lowest discretized score above the given score.
+    fn ceil_index(&self, score: f32) -> i64 {
+        // tolerate the rounding errors of scores computed in single precision
+        let x = score as f64 / self.granularity;
+        self.index((x - 1e-4).ceil())
+    }
+
+    /// The probability of the discretized score closest to the given score.
+    pub fn pmf(&self, score: f32) -> f64 {
+        let i = self.index((score as f64 / self.granularity).round());
+        if i < 0 || i >= self.pmf.len() as i64 {
+            0.0
+        } else {
+            self.pmf[i as usize]
+        }
+    }
+
+    /// The probability of a random sequence scoring at most the given score.
+    pub fn cdf(&self, score: f32) -> f64 {
+        let total = self.sf.first().cloned().unwrap_or(0.0);
+        let i = self.index((score as f64 / self.granularity + 1e-4).floor()) + 1;
+        if i <= 0 {
+            0.0
+        } else if i >= self.sf.len() as i64 {
+            total
+        } else {
+            total - self.sf[i as usize]
+        }
+    }
+
+    /// The probability of a random sequence scoring at least the given score.
+    pub fn pvalue(&self, score: f32) -> f64 {
+        let i = self.ceil_index(score);
+        if i <= 0 {
+            self.sf.first().cloned().unwrap_or(0.0)
+        } else if i >= self.sf.len() as i64 {
+            0.0
+        } else {
+            self.sf[i as usize]
+        }
+    }
+
+    /// The lowest score with a p-value at most the given p-value.
+    ///
+    /// Returns positive infinity if even the highest score is too frequent.
+    pub fn score(&self, pvalue: f64) -> f32 {
+        // `sf` is decreasing, find the first index with a small enough p-value
+        let i = self.sf.partition_point(|&p| p > pvalue);
+        if i >= self.sf.len() {
+            f32::INFINITY
+        } else {
+            ((self.offset + i as i64) as f64 * self.granularity) as f32
+        }
+    }
+
+    /// The mean of the finite scores.
+    pub fn mean(&self) -> f64 {
+        let total: f64 = self.pmf.iter().sum();
+        self.iter().map(|(s, p)| s as f64 * p).sum::<f64>() / total
+    }
+
+    /// The variance of the finite scores.
+    pub fn variance(&self) -> f64 {
+        let total: f64 = self.pmf.iter().sum();
+        let mean = self.mean();
+        self.iter()
+            .map(|(s, p)| (s as f64 - mean).powi(2) * p)
+            .sum::<f64>()
+            / total
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ln_gamma() {
+        assert!(ln_gamma(1.0).abs() < 1e-12);
+        assert!(ln_gamma(2.0).abs() < 1e-12);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+        assert!((ln_gamma(0.5) - std::f64::consts::PI.sqrt().ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fisher_exact() {
+        // lady tasting tea
+        let p = fisher_exact(3, 1, 1, 3).exp();
+        assert!((p - 17.0 / 70.0).abs() < 1e-9, "{}", p);
+        assert!(fisher_exact(0, 4, 4, 0).abs() < 1e-12);
+        assert!(fisher_exact(50, 0, 0, 50) < -60.0);
+    }
+
+    #[test]
+    fn test_binomial_test() {
+        let p = binomial_test(8, 10, 0.5).exp();
+        assert!((p - 56.0 / 1024.0).abs() < 1e-9, "{}", p);
+        assert_eq!(binomial_test(0, 10, 0.5), 0.0);
+        assert!(binomial_test(1000, 1000, 0.5) < -690.0);
+    }
+
+    #[test]
+    fn test_ln_normal_sf() {
+        assert!((ln_normal_sf(0.0).exp() - 0.5).abs() < 1e-7);
+        assert!((ln_normal_sf(1.959964).exp() - 0.025).abs() < 1e-7);
+        assert!((ln_normal_sf(-1.959964).exp() - 0.975).abs() < 1e-7);
+        // log(Q(40)) computed with mpmath
+        assert!((ln_normal_sf(40.0) - -804.608_442_013_754).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_benjamini_hochberg() {
+        let pvalues = [0.01f64, 0.04, 0.03, 0.5];
+        let log_pvalues = pvalues.iter().map(|p| p.ln()).collect::<Vec<_>>();
+        let adjusted = benjamini_hochberg(&log_pvalues)
+            .into_iter()
+            .map(f64::exp)
+            .collect::<Vec<_>>();
+        let expected = [0.04, 0.16 / 3.0, 0.16 / 3.0, 0.5];
+        for (q, e) in adjusted.iter().zip(expected) {
+            assert!((q - e).abs() < 1e-9, "{} != {}", q, e);
+        }
+
+        let adjusted = benjamini_hochberg(&[f64::NAN, 0.01f64.ln()]);
+        assert_eq!(adjusted.len(), 2);
+        assert!((adjusted[1].exp() - 0.02).abs() < 1e-9, "{}", adjusted[1]);
+    }
+
+    fn build_pssm() -> ScoringMatrix<crate::abc::Dna> {
+        use crate::pwm::CountMatrix;
+        use crate::seq::EncodedSequence;
+
+        let sites = ["GTTGACCTTATCAAC", "GTTGATCCAGTCAAC"];
+        CountMatrix::from_sequences(sites.iter().map(|s| EncodedSequence::encode(s).unwrap()))
+            .unwrap()
+            .to_freq(0.1)
+            .to_scoring(None)
+    }
+
+    #[test]
+    fn test_score_distribution() {
+        use crate::abc::Dna;
+        use crate::abc::Symbol;
+        use crate::pwm::CountMatrix;
+        use crate::seq::EncodedSequence;
+
+        let pssm = build_pssm();
+        let dist = ScoreDistribution::new(&pssm, None, 0.01);
+        let total: f64 = dist.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!((dist.max_score() - pssm.max_score()).abs() < 0.01 * 15.0);
+        assert_eq!(dist.pvalue(dist.max_score()), dist.pmf(dist.max_score()));
+        assert!(dist.pvalue(dist.max_score()) >= 0.25f64.powi(15));
+        assert_eq!(dist.pvalue(dist.max_score() + 1.0), 0.0);
+        assert_eq!(dist.pvalue(dist.min_score() - 1.0), 1.0);
+        assert_eq!(dist.cdf(dist.max_score()), 1.0);
+        assert_eq!(dist.cdf(dist.min_score() - 1.0), 0.0);
+        assert_eq!(dist.pvalue(f32::INFINITY), 0.0);
+        assert_eq!(dist.pvalue(f32::NEG_INFINITY), 1.0);
+        assert_eq!(dist.cdf(f32::INFINITY), 1.0);
+        assert_eq!(dist.cdf(f32::NEG_INFINITY), 0.0);
+        assert_eq!(dist.pmf(f32::INFINITY), 0.0);
+        assert_eq!(dist.pmf(f32::NEG_INFINITY), 0.0);
+        for (s, _) in dist.iter().filter(|x| x.1 > 0.0).step_by(97) {
+            let total = dist.cdf(s) + dist.pvalue(s) - dist.pmf(s);
+            assert!((total - 1.0).abs() < 1e-9, "{}", total);
+            assert!(dist.pvalue(dist.score(dist.pvalue(s))) <= dist.pvalue(s));
+            assert_eq!(dist.score(dist.pvalue(s)), s);
+        }
+
+        // compare the exact moments of the matrix under a uniform background
+        let weights = pssm.weights();
+        let mut mean = 0.0;
+        let mut variance = 0.0;
+        let mut min = 0.0;
+        for row in weights.iter() {
+            let known = Dna::symbols()
+                .iter()
+                .filter(|s| s.as_index() != Dna::default_symbol().as_index())
+                .map(|s| row[s.as_index()] as f64)
+                .collect::<Vec<_>>();
+            let m = known.iter().sum::<f64>() / 4.0;
+            mean += m;
+            min += known.iter().cloned().fold(f64::INFINITY, f64::min);
+            variance += known.iter().map(|x| (x - m).powi(2)).sum::<f64>() / 4.0;
+        }
+        assert!((dist.min_score() as f64 - min).abs() < 0.01 * 15.0);
+        assert!((dist.mean() - mean).abs() < 0.01 * 15.0);
+        assert!((dist.variance() - variance).abs() / variance < 1e-2);
+
+        // compare to an exhaustive enumeration on a short matrix
+        let short = CountMatrix::<Dna>::from_sequences(
+            ["TAC", "TGC", "AAC"]
+                .iter()
+                .map(|s| EncodedSequence::encode(s).unwrap()),
+        )
+        .unwrap()
+        .to_freq(0.5)
+        .to_scoring(None);
+        let dist = ScoreDistribution::new(&short, None, 0.001);
+        let mut hits = 0;
+        let threshold = 1.5;
+        for i in 0..64usize {
+            let seq = (0..3)
+                .map(|k| ['A', 'C', 'G', 'T'][(i >> (2 * k)) & 3])
+                .collect::<String>();
+            let seq = EncodedSequence::<Dna>::encode(&seq).unwrap();
+            let score: f32 = (0..3).map(|k| short.weights()[k][seq[k].as_index()]).sum();
+            if score >= threshold {
+                hits += 1;
+            }
+        }
+        assert!((dist.pvalue(threshold) - hits as f64 / 64.0).abs() < 1e-12);
+    }
+}
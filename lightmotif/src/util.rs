@@ -0,0 +1,84 @@
+//! Private helpers shared by several modules.
+
+use super::abc::Alphabet;
+use super::pwm::ScoringMatrix;
+
+// --- Union-find --------------------------------------------------------------
+
+/// Find the root of an element in a union-find forest.
+///
+/// Paths are compressed by halving while the forest is traversed.
+pub(crate) fn find(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
+
+// --- Bounds ------------------------------------------------------------------
+
+/// Compute the maximum score reachable after each of the given columns.
+///
+/// The bound at index `k` is the sum of the maximum weights of the columns
+/// in `columns[k..]`, so the last bound is always zero.
+pub(crate) fn bounds<A: Alphabet>(pssm: &ScoringMatrix<A>, columns: &[usize]) -> Vec<f32> {
+    let weights = pssm.weights();
+    let mut bounds = vec![0.0; columns.len() + 1];
+    for k in (0..columns.len()).rev() {
+        let max = weights[columns[k]]
+            .iter()
+            .cloned()
+            .fold(f32::NEG_INFINITY, f32::max);
+        bounds[k] = bounds[k + 1] + max;
+    }
+    bounds
+}
+
+/// Compute the tolerance on bounds for rounding errors.
+///
+/// Bounds and partial scores are summed in a different order than the
+/// scores of the pipelines, so pruning a prefix must tolerate the rounding
+/// errors of a sum over all the columns of the matrix.
+pub(crate) fn tolerance<A: Alphabet>(pssm: &ScoringMatrix<A>) -> f32 {
+    let magnitude = pssm
+        .weights()
+        .iter()
+        .flat_map(|row| row.iter())
+        .filter(|x| x.is_finite())
+        .map(|x| x.abs())
+        .fold(0.0, f32::max);
+    4.0 * f32::EPSILON * magnitude * pssm.len() as f32
+}
+
+// --- Fixtures ----------------------------------------------------------------
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::abc::Dna;
+    use crate::pwm::CountMatrix;
+    use crate::pwm::ScoringMatrix;
+    use crate::seq::EncodedSequence;
+
+    /// A scoring matrix built from three aligned sites.
+    pub(crate) fn pssm() -> ScoringMatrix<Dna> {
+        let sites = ["GTTGACCTTATCAAC", "GTTGATCCAGTCAAC", "GTTGACCAATTCAAC"];
+        CountMatrix::from_sequences(sites.iter().map(|s| EncodedSequence::encode(s).unwrap()))
+            .unwrap()
+            .to_freq(0.1)
+            .to_scoring(None)
+    }
+
+    /// A pseudo-random sequence, including unknown nucleotides.
+    pub(crate) fn sequence(length: usize, seed: u64) -> EncodedSequence<Dna> {
+        let mut state = seed;
+        let mut text = String::with_capacity(length);
+        for _ in 0..length {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            text.push(b"ACGTN"[((state >> 33) % 5) as usize] as char);
+        }
+        EncodedSequence::encode(&text).unwrap()
+    }
+}
@@ -0,0 +1,327 @@
+//! Effect of sequence variants on motif binding.
+//!
+//! A [`Variant`] replaces a reference allele with an alternate allele at a
+//! given position of a sequence, which covers SNVs as well as small
+//! insertions and deletions. Only the windows overlapping the variant can
+//! change score, so [`Variant::effects`] rescans only these windows, on
+//! both strands, in the reference and in the alternate sequence, to report
+//! motif gains and losses.
+//!
+//! P-values of the reported scores can be obtained from the
+//! `lightmotif-tfmpvalue` crate, which depends on this one.
+
+use std::str::FromStr;
+
+use super::abc::Alphabet;
+use super::abc::ComplementableAlphabet;
+use super::abc::Symbol;
+use super::err::InvalidData;
+use super::err::InvalidSymbol;
+use super::num::StrictlyPositive;
+use super::pli::Score;
+use super::pwm::ScoringMatrix;
+use super::seq::EncodedSequence;
+use super::seq::Strand;
+
+// --- Variant -----------------------------------------------------------------
+
+/// A sequence variant, replacing a reference allele with an alternate allele.
+#[derive(Clone, Debug)]
+pub struct Variant<A: Alphabet> {
+    position: usize,
+    reference: EncodedSequence<A>,
+    alternate: EncodedSequence<A>,
+}
+
+impl<A: Alphabet> Variant<A> {
+    /// Create a new variant at the given position.
+    ///
+    /// Any of the alleles may be empty to represent a pure insertion or
+    /// a pure deletion.
+    pub fn new(
+        position: usize,
+        reference: EncodedSequence<A>,
+        alternate: EncodedSequence<A>,
+    ) -> Self {
+        Self {
+            position,
+            reference,
+            alternate,
+        }
+    }
+
+    /// Create a new variant by encoding the given alleles.
+    pub fn encode(
+        position: usize,
+        reference: &str,
+        alternate: &str,
+    ) -> Result<Self, InvalidSymbol> {
+        Ok(Self::new(
+            position,
+            EncodedSequence::from_str(reference)?,
+            EncodedSequence::from_str(alternate)?,
+        ))
+    }
+
+    /// The position of the variant in the reference sequence.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The reference allele.
+    pub fn reference(&self) -> &EncodedSequence<A> {
+        &self.reference
+    }
+
+    /// The alternate allele.
+    pub fn alternate(&self) -> &EncodedSequence<A> {
+        &self.alternate
+    }
+
+    /// Check that the reference allele is found in the given sequence.
+    fn check(&self, sequence: &EncodedSequence<A>) -> Result<(), InvalidData> {
+        let end = self.position + self.reference.len();
+        if end > sequence.len() {
+            return Err(InvalidData);
+        }
+        let observed = sequence[self.position..end].iter();
+        if observed
+            .zip(self.reference.iter())
+            .all(|(x, y)| x.as_index() == y.as_index())
+        {
+            Ok(())
+        } else {
+            Err(InvalidData)
+        }
+    }
+
+    /// Apply the variant to the given reference sequence.
+    ///
+    /// # Errors
+    /// Returns an error if the reference allele does not match the sequence.
+    pub fn apply(&self, sequence: &EncodedSequence<A>) -> Result<EncodedSequence<A>, InvalidData> {
+        self.check(sequence)?;
+        let end = self.position + self.reference.len();
+        Ok(sequence
+            .slice(0..self.position)
+            .concat(&self.alternate)
+            .concat(&sequence.slice(end..sequence.len())))
+    }
+}
+
+impl<A: ComplementableAlphabet> Variant<A> {
+    /// Compute the effect of the variant on the given scoring matrices.
+    ///
+    /// For each matrix, the windows overlapping the variant are scored on
+    /// both strands, in the reference sequence and in the sequence with the
+    /// alternate allele, and the best site of each allele is reported.
+    ///
+    /// # Errors
+    /// Returns an error if the reference allele does not match the sequence.
+    pub fn effects<C, P>(
+        &self,
+        pipeline: &P,
+        sequence: &EncodedSequence<A>,
+        matrices: &[ScoringMatrix<A>],
+    ) -> Result<Vec<VariantEffect>, InvalidData>
+    where
+        C: StrictlyPositive,
+        P: Score<A, C>,
+    {
+        let alternate = self.apply(sequence)?;
+        Ok(matrices
+            .iter()
+            .enumerate()
+            .map(|(index, pssm)| {
+                let reference = best_hit(
+                    pipeline,
+                    pssm,
+                    sequence,
+                    self.position,
+                    self.reference.len(),
+                );
+                let alternate = best_hit(
+                    pipeline,
+                    pssm,
+                    &alternate,
+                    self.position,
+                    self.alternate.len(),
+                );
+                VariantEffect {
+                    index,
+                    reference,
+                    alternate,
+                }
+            })
+            .collect())
+    }
+}
+
+// --- VariantEffect -----------------------------------------------------------
+
+/// The best site of a motif overlapping a variant allele.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    /// The start of the site on the direct strand of the allele sequence.
+    pub position: usize,
+    /// The strand of the site.
+    pub strand: Strand,
+    /// The score of the site.
+    pub score: f32,
+}
+
+/// The effect of a variant on the best site of a motif.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantEffect {
+    /// The index of the scoring matrix.
+    pub index: usize,
+    /// The best site overlapping the reference allele, if any.
+    pub reference: Option<Hit>,
+    /// The best site overlapping the alternate allele, if any.
+    pub alternate: Option<Hit>,
+}
+
+impl VariantEffect {
+    /// The score difference between the alternate and the reference sites.
+    ///
+    /// A positive value indicates a motif gain, a negative value indicates a
+    /// motif loss. Returns `None` if one of the alleles has no site.
+    pub fn delta(&self) -> Option<f32> {
+        match (&self.reference, &self.alternate) {
+            (Some(r), Some(a)) => Some(a.score - r.score),
+            _ => None,
+        }
+    }
+}
+
+/// Find the best site overlapping the allele at `position..position+length`.
+///
+/// Empty alleles are overlapped by the windows spanning the junction
+/// between `position - 1` and `position`.
+fn best_hit<A, C, P>(
+    pipeline: &P,
+    pssm: &ScoringMatrix<A>,
+    sequence: &EncodedSequence<A>,
+    position: usize,
+    length: usize,
+) -> Option<Hit>
+where
+    A: ComplementableAlphabet,
+    C: StrictlyPositive,
+    P: Score<A, C>,
+{
+    let w = pssm.len();
+    let start = (position + 1).saturating_sub(w);
+    let end = (position + length + w - 1).min(sequence.len());
+    if end < start + w {
+        return None;
+    }
+    let region = sequence.slice(start..end);
+    let windows = region.len() - w + 1;
+    let overlaps = |i: usize| start + i < position + length && start + i + w > position;
+
+    let mut best: Option<Hit> = None;
+    for strand in [Strand::Direct, Strand::Reverse] {
+        let seq = match strand {
+            Strand::Direct => region.clone(),
+            Strand::Reverse => region.reverse_complement(),
+        };
+        let mut striped = seq.to_striped::<C>();
+        striped.configure(pssm);
+        let scores = pipeline.score(&striped, pssm);
+        for j in 0..windows {
+            let i = match strand {
+                Strand::Direct => j,
+                Strand::Reverse => windows - j - 1,
+            };
+            if overlaps(i) && best.as_ref().map(|b| scores[j] > b.score).unwrap_or(true) {
+                best = Some(Hit {
+                    position: start + i,
+                    strand,
+                    score: scores[j],
+                });
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use typenum::consts::U32;
+
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::pli::Pipeline;
+    use crate::pwm::CountMatrix;
+
+    fn pssm() -> ScoringMatrix<Dna> {
+        CountMatrix::from_sequences([EncodedSequence::encode("TTGACGCA").unwrap()])
+            .unwrap()
+            .to_freq(0.25)
+            .to_scoring(None)
+    }
+
+    #[test]
+    fn test_apply() {
+        let seq = EncodedSequence::<Dna>::encode("ACGTACGT").unwrap();
+        let snv = Variant::encode(2, "G", "T").unwrap();
+        assert_eq!(snv.apply(&seq).unwrap().to_string(), "ACTTACGT");
+        let insertion = Variant::encode(4, "", "GG").unwrap();
+        assert_eq!(insertion.apply(&seq).unwrap().to_string(), "ACGTGGACGT");
+        let deletion = Variant::encode(1, "CGT", "").unwrap();
+        assert_eq!(deletion.apply(&seq).unwrap().to_string(), "AACGT");
+        let wrong = Variant::encode(1, "A", "T").unwrap();
+        assert!(wrong.apply(&seq).is_err());
+        let outside = Variant::encode(7, "TA", "T").unwrap();
+        assert!(outside.apply(&seq).is_err());
+    }
+
+    #[test]
+    fn test_snv_loss() {
+        let pli = Pipeline::<Dna, _>::generic();
+        let pssm = pssm();
+        let seq = EncodedSequence::encode("GGGGGTTGACGCAGGGGG").unwrap();
+        let variant = Variant::encode(9, "C", "T").unwrap();
+        let effects = variant
+            .effects::<U32, _>(&pli, &seq, &[pssm.clone()])
+            .unwrap();
+        let effect = &effects[0];
+        let reference = effect.reference.as_ref().unwrap();
+        assert_eq!(reference.position, 5);
+        assert_eq!(reference.strand, Strand::Direct);
+        let expected = pli.score(&seq.to_striped::<U32>(), &pssm)[5];
+        assert_eq!(reference.score, expected);
+        assert!(effect.delta().unwrap() < -2.0);
+    }
+
+    #[test]
+    fn test_reverse_gain() {
+        let pli = Pipeline::<Dna, _>::generic();
+        let pssm = pssm();
+        // TGCGTCAA is the reverse complement of the motif
+        let seq = EncodedSequence::encode("AAAAATGCGTGAAAAAAAA").unwrap();
+        let variant = Variant::encode(10, "G", "CA").unwrap();
+        let effects = variant.effects::<U32, _>(&pli, &seq, &[pssm]).unwrap();
+        let alternate = effects[0].alternate.as_ref().unwrap();
+        assert_eq!(alternate.position, 5);
+        assert_eq!(alternate.strand, Strand::Reverse);
+        assert!(effects[0].delta().unwrap() > 2.0);
+    }
+
+    #[test]
+    fn test_deletion_junction() {
+        let pli = Pipeline::<Dna, _>::generic();
+        let pssm = pssm();
+        let seq = EncodedSequence::encode("CCCCTTGACCCCGCACCCC").unwrap();
+        let variant = Variant::encode(9, "CCC", "").unwrap();
+        let effects = variant
+            .effects::<U32, _>(&pli, &seq, &[pssm.clone()])
+            .unwrap();
+        let alternate = effects[0].alternate.as_ref().unwrap();
+        assert_eq!(alternate.position, 4);
+        assert_eq!(alternate.strand, Strand::Direct);
+        assert_eq!(alternate.score, pssm.max_score());
+    }
+}
@@ -0,0 +1,193 @@
+//! Sequence weighting schemes to reduce the redundancy of a set of sites.
+//!
+//! Sites collected from homologous families are often redundant, which
+//! biases the counts towards the most represented family. The weights
+//! computed here can be passed to [`CountMatrix::from_weighted_sequences`]
+//! to build a count matrix where redundant sites are down-weighted.
+//!
+//! All weights are scaled so that they sum to the number of sequences,
+//! which keeps the total counts of the resulting matrix comparable to an
+//! unweighted matrix built from the same sequences.
+//!
+//! [`CountMatrix::from_weighted_sequences`]: crate::pwm::CountMatrix::from_weighted_sequences
+
+use super::abc::Alphabet;
+use super::abc::Symbol;
+use super::err::InvalidData;
+use super::num::Unsigned;
+use super::seq::EncodedSequence;
+use super::util::find;
+
+/// Scale weights so that they sum to the number of weights.
+fn normalize(weights: &mut [f32]) {
+    let total: f32 = weights.iter().sum();
+    if total > 0.0 {
+        let scale = weights.len() as f32 / total;
+        for w in weights.iter_mut() {
+            *w *= scale;
+        }
+    }
+}
+
+/// Check that all sequences have the same length, and return it.
+fn common_length<A: Alphabet, S: AsRef<EncodedSequence<A>>>(
+    sequences: &[S],
+) -> Result<usize, InvalidData> {
+    let length = sequences.first().map(|s| s.as_ref().len()).unwrap_or(0);
+    if sequences.iter().all(|s| s.as_ref().len() == length) {
+        Ok(length)
+    } else {
+        Err(InvalidData)
+    }
+}
+
+/// Compute the fraction of identical symbols between two sequences.
+///
+/// Positions where either sequence has the wildcard symbol are ignored.
+fn identity<A: Alphabet>(x: &EncodedSequence<A>, y: &EncodedSequence<A>) -> f32 {
+    let wildcard = A::default_symbol().as_index();
+    let mut compared = 0;
+    let mut identical = 0;
+    for (a, b) in x
+        .into_iter()
+        .zip(y)
+        .map(|(a, b)| (a.as_index(), b.as_index()))
+    {
+        if a != wildcard && b != wildcard {
+            compared += 1;
+            if a == b {
+                identical += 1;
+            }
+        }
+    }
+    if compared == 0 {
+        0.0
+    } else {
+        identical as f32 / compared as f32
+    }
+}
+
+/// Compute position-based sequence weights from Henikoff & Henikoff (1994).
+///
+/// At each position with `r` distinct symbols, a sequence with a symbol
+/// observed `k` times receives a weight of `1 / (r * k)`, and the weight
+/// of a sequence is the sum of its weights over all positions. Wildcard
+/// symbols are ignored. Sequences must all have the same length.
+pub fn henikoff<A: Alphabet, S: AsRef<EncodedSequence<A>>>(
+    sequences: &[S],
+) -> Result<Vec<f32>, InvalidData> {
+    let length = common_length(sequences)?;
+    let wildcard = A::default_symbol().as_index();
+    let mut weights = vec![0.0; sequences.len()];
+    let mut counts = vec![0usize; A::K::USIZE];
+    for i in 0..length {
+        counts.iter_mut().for_each(|c| *c = 0);
+        for seq in sequences {
+            counts[seq.as_ref()[i].as_index()] += 1;
+        }
+        counts[wildcard] = 0;
+        let r = counts.iter().filter(|&&c| c > 0).count();
+        for (seq, w) in sequences.iter().zip(weights.iter_mut()) {
+            let s = seq.as_ref()[i].as_index();
+            if s != wildcard {
+                *w += 1.0 / (r * counts[s]) as f32;
+            }
+        }
+    }
+    normalize(&mut weights);
+    Ok(weights)
+}
+
+/// Compute sequence weights by clustering sequences at an identity threshold.
+///
+/// Sequences are clustered with single linkage, two sequences being linked
+/// if the fraction of identical symbols between them is at least
+/// `threshold`, as done to build the BLOSUM matrices. Each cluster then
+/// receives the same total weight, shared evenly between its members.
+/// Sequences must all have the same length.
+pub fn clustered<A: Alphabet, S: AsRef<EncodedSequence<A>>>(
+    sequences: &[S],
+    threshold: f32,
+) -> Result<Vec<f32>, InvalidData> {
+    common_length(sequences)?;
+    let n = sequences.len();
+    let mut parent = (0..n).collect::<Vec<usize>>();
+    for i in 0..n {
+        for j in i + 1..n {
+            if identity(sequences[i].as_ref(), sequences[j].as_ref()) >= threshold {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                parent[ri.max(rj)] = ri.min(rj);
+            }
+        }
+    }
+    let mut sizes = vec![0usize; n];
+    for i in 0..n {
+        sizes[find(&mut parent, i)] += 1;
+    }
+    let mut weights = (0..n)
+        .map(|i| 1.0 / sizes[find(&mut parent, i)] as f32)
+        .collect::<Vec<f32>>();
+    normalize(&mut weights);
+    Ok(weights)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::abc::Dna;
+    use crate::abc::Nucleotide;
+    use crate::abc::Protein;
+    use crate::pwm::CountMatrix;
+
+    fn encode<A: Alphabet>(sequences: &[&str]) -> Vec<EncodedSequence<A>> {
+        sequences
+            .iter()
+            .map(|s| EncodedSequence::encode(s).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_henikoff() {
+        let sequences = encode::<Protein>(&["GYVGS", "GFDGF", "GYDGF", "GYQGG"]);
+        let weights = henikoff(&sequences).unwrap();
+        let expected = [16.0 / 15.0, 16.0 / 15.0, 12.0 / 15.0, 16.0 / 15.0];
+        for (w, e) in weights.iter().zip(expected) {
+            assert!((w - e).abs() < 1e-5, "{} != {}", w, e);
+        }
+        assert!((weights.iter().sum::<f32>() - 4.0).abs() < 1e-5);
+
+        let sequences = encode::<Dna>(&["ACGT", "ACG"]);
+        assert!(henikoff(&sequences).is_err());
+    }
+
+    #[test]
+    fn test_clustered() {
+        let sequences = encode::<Dna>(&["ACGTACGT", "ACGTACGA", "TTTTGGGG"]);
+        let weights = clustered(&sequences, 0.8).unwrap();
+        assert_eq!(weights, vec![0.75, 0.75, 1.5]);
+        let weights = clustered(&sequences, 0.9).unwrap();
+        assert_eq!(weights, vec![1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_from_weighted_sequences() {
+        let sequences = encode::<Dna>(&["ACGTACGT", "ACGTACGA", "TTTTGGGG"]);
+        let weights = clustered(&sequences, 0.8).unwrap();
+        let counts =
+            CountMatrix::<Dna, f32>::from_weighted_sequences(sequences.iter().zip(weights))
+                .unwrap();
+        assert_eq!(counts[0][Nucleotide::A.as_index()], 1.5);
+        assert_eq!(counts[0][Nucleotide::T.as_index()], 1.5);
+        assert_eq!(counts[7][Nucleotide::T.as_index()], 0.75);
+        assert_eq!(counts[7][Nucleotide::A.as_index()], 0.75);
+        assert_eq!(counts[7][Nucleotide::G.as_index()], 1.5);
+
+        for weight in [-1.0, f32::NAN, f32::INFINITY] {
+            let weights = [1.0, weight, 1.0];
+            let result =
+                CountMatrix::<Dna, f32>::from_weighted_sequences(sequences.iter().zip(weights));
+            assert!(result.is_err());
+        }
+    }
+}
@@ -2,10 +2,12 @@ extern crate lightmotif;
 extern crate typenum;
 
 use lightmotif::abc::Dna;
+use lightmotif::abc::Symbol;
 use lightmotif::num::StrictlyPositive;
 use lightmotif::num::U1;
 use lightmotif::num::U16;
 use lightmotif::num::U32;
+use lightmotif::pli::Affinity;
 use lightmotif::pli::BestPosition;
 use lightmotif::pli::Pipeline;
 use lightmotif::pli::Score;
@@ -101,6 +103,107 @@ fn test_threshold<C: StrictlyPositive, P: Score<Dna, C> + Threshold<C>>(pli: &P)
     assert_eq!(positions, vec![10, 13, 14, 18, 24, 27, 32, 35, 40, 47]);
 }
 
+fn test_affinity<C: StrictlyPositive, P: Score<Dna, C> + Affinity<C>>(pli: &P) {
+    let mut striped = StripedSequence::<Dna, C>::encode(SEQUENCE).unwrap();
+
+    let cm = CountMatrix::<Dna>::from_sequences(
+        PATTERNS.iter().map(|x| EncodedSequence::encode(x).unwrap()),
+    )
+    .unwrap();
+    let pbm = cm.to_freq(0.1);
+    let pwm = pbm.to_weight(None);
+    let pssm = pwm.into();
+
+    striped.configure(&pssm);
+    let result = pli.score(&striped, &pssm);
+
+    for (range, temperature) in [(0..EXPECTED.len(), 1.0), (10..30, 1.0), (5..45, 2.5)] {
+        let sum: f64 = EXPECTED[range.clone()]
+            .iter()
+            .map(|&x| (x as f64 / temperature).exp2())
+            .sum();
+        let expected = temperature * sum.log2();
+        let lse = pli.log_sum_exp(&result, range.clone(), temperature as f32);
+        assert!(
+            (lse as f64 - expected).abs() < 1e-4,
+            "{} != {}",
+            lse,
+            expected
+        );
+
+        let expected: f64 = EXPECTED[range.clone()]
+            .iter()
+            .map(|&x| {
+                let w = 4.0 * (x as f64 / temperature).exp2();
+                w / (1.0 + w)
+            })
+            .sum();
+        let occupancy = pli.occupancy(&result, range, temperature as f32, 4.0);
+        assert!(
+            (occupancy as f64 - expected).abs() < 1e-4 * expected,
+            "{} != {}",
+            occupancy,
+            expected
+        );
+    }
+
+    assert_eq!(pli.log_sum_exp(&result, 20..20, 1.0), f32::NEG_INFINITY);
+    assert_eq!(pli.occupancy(&result, 20..20, 1.0, 1.0), 0.0);
+    assert_eq!(
+        pli.log_sum_exp(&result, 0..1000, 1.0),
+        pli.log_sum_exp(&result, 0..EXPECTED.len(), 1.0)
+    );
+
+    // score the reverse strand on the reverse complement, and compare with
+    // the reverse complement of each window scored column by column
+    let seq = EncodedSequence::<Dna>::encode(SEQUENCE).unwrap();
+    let mut striped = seq.reverse_complement().to_striped::<C>();
+    striped.configure(&pssm);
+    let reverse = pli.score(&striped, &pssm);
+    let weights = pssm.weights();
+    let rc = (0..EXPECTED.len())
+        .map(|i| {
+            seq.slice(i..i + pssm.len())
+                .reverse_complement()
+                .iter()
+                .into_iter()
+                .enumerate()
+                .map(|(j, x)| weights[j][x.as_index()] as f64)
+                .sum::<f64>()
+        })
+        .collect::<Vec<f64>>();
+    for (range, temperature) in [(0..EXPECTED.len(), 1.0), (10..30, 1.0), (5..45, 2.5)] {
+        let sum: f64 = range
+            .clone()
+            .map(|i| (EXPECTED[i] as f64 / temperature).exp2() + (rc[i] / temperature).exp2())
+            .sum();
+        let expected = temperature * sum.log2();
+        let lse = pli.log_sum_exp_strands(&result, &reverse, range.clone(), temperature as f32);
+        assert!(
+            (lse as f64 - expected).abs() < 1e-4,
+            "{} != {}",
+            lse,
+            expected
+        );
+
+        let expected: f64 = range
+            .clone()
+            .flat_map(|i| [EXPECTED[i] as f64, rc[i]])
+            .map(|x| {
+                let w = 4.0 * (x / temperature).exp2();
+                w / (1.0 + w)
+            })
+            .sum();
+        let occupancy = pli.occupancy_strands(&result, &reverse, range, temperature as f32, 4.0);
+        assert!(
+            (occupancy as f64 - expected).abs() < 1e-4 * expected,
+            "{} != {}",
+            occupancy,
+            expected
+        );
+    }
+}
+
 #[test]
 fn test_score_generic() {
     let pli = Pipeline::generic();
@@ -121,6 +224,78 @@ fn test_threshold_generic() {
     test_threshold::<U32, _>(&pli);
 }
 
+#[test]
+fn test_affinity_generic() {
+    let pli = Pipeline::generic();
+    test_affinity::<U32, _>(&pli);
+    test_affinity::<U1, _>(&pli);
+}
+
+#[test]
+fn test_score_table() {
+    let pli = Pipeline::table();
+    test_score::<U32, _>(&pli);
+    test_score::<U1, _>(&pli);
+}
+
+#[test]
+fn test_score_table_lengths() {
+    let generic = Pipeline::generic();
+    let table = Pipeline::table();
+    let mut striped = StripedSequence::<Dna, U32>::encode(SEQUENCE).unwrap();
+    for n in 1..=PATTERNS[0].len() {
+        let cm = CountMatrix::<Dna>::from_sequences(
+            PATTERNS
+                .iter()
+                .map(|x| EncodedSequence::encode(&x[..n]).unwrap()),
+        )
+        .unwrap();
+        let pssm = cm.to_freq(0.1).to_scoring(None);
+        striped.configure(&pssm);
+        let expected = generic.score(&striped, &pssm).to_vec();
+        let scores = table.score(&striped, &pssm).to_vec();
+        assert_eq!(scores.len(), expected.len());
+        for i in 0..scores.len() {
+            assert!((scores[i] - expected[i]).abs() < 1e-4, "length {}", n);
+        }
+    }
+
+    // unknown nucleotides are scored column by column
+    let cm = CountMatrix::<Dna>::from_sequences(
+        PATTERNS.iter().map(|x| EncodedSequence::encode(x).unwrap()),
+    )
+    .unwrap();
+    let bg = lightmotif::abc::Background::new([0.25, 0.25, 0.25, 0.2, 0.05]).unwrap();
+    let pssm = cm.to_freq(0.1).to_scoring(bg);
+    let mut striped =
+        StripedSequence::<Dna, U32>::encode(&SEQUENCE.replace("CCCC", "CNNC")).unwrap();
+    striped.configure(&pssm);
+    let expected = generic.score(&striped, &pssm).to_vec();
+    let scores = table.score(&striped, &pssm).to_vec();
+    for i in 0..scores.len() {
+        assert!(scores[i] == expected[i] || (scores[i] - expected[i]).abs() < 1e-4);
+    }
+    assert!(scores.iter().any(|x| x.is_infinite()));
+}
+
+#[test]
+fn test_best_position_table() {
+    let pli = Pipeline::table();
+    test_best_position::<U32, _>(&pli);
+}
+
+#[test]
+fn test_threshold_table() {
+    let pli = Pipeline::table();
+    test_threshold::<U32, _>(&pli);
+}
+
+#[test]
+fn test_affinity_table() {
+    let pli = Pipeline::table();
+    test_affinity::<U32, _>(&pli);
+}
+
 #[cfg(target_feature = "sse2")]
 #[test]
 fn test_score_sse2() {
@@ -142,6 +317,13 @@ fn test_threshold_sse2() {
     test_threshold::<U16, _>(&pli);
 }
 
+#[cfg(target_feature = "sse2")]
+#[test]
+fn test_affinity_sse2() {
+    let pli = Pipeline::sse2().unwrap();
+    test_affinity::<U16, _>(&pli);
+}
+
 #[cfg(target_feature = "sse2")]
 #[test]
 fn test_score_sse2_32() {
@@ -163,6 +345,13 @@ fn test_threshold_sse2_32() {
     test_threshold::<U32, _>(&pli);
 }
 
+#[cfg(target_feature = "sse2")]
+#[test]
+fn test_affinity_sse2_32() {
+    let pli = Pipeline::sse2().unwrap();
+    test_affinity::<U32, _>(&pli);
+}
+
 #[cfg(target_feature = "avx2")]
 #[test]
 fn test_score_avx2() {
@@ -184,6 +373,13 @@ fn test_threshold_avx2() {
     test_threshold::<U32, _>(&pli);
 }
 
+#[cfg(target_feature = "avx2")]
+#[test]
+fn test_affinity_avx2() {
+    let pli = Pipeline::avx2().unwrap();
+    test_affinity(&pli);
+}
+
 #[cfg(target_feature = "neon")]
 #[test]
 fn test_score_neon() {
@@ -204,3 +400,10 @@ fn test_threshold_neon() {
     let pli = Pipeline::neon().unwrap();
     test_threshold::<U16, _>(&pli);
 }
+
+#[cfg(target_feature = "neon")]
+#[test]
+fn test_affinity_neon() {
+    let pli = Pipeline::neon().unwrap();
+    test_affinity::<U16, _>(&pli);
+}